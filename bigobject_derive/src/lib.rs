@@ -50,6 +50,12 @@ pub fn derive_big_object(input: proc_macro::TokenStream) -> proc_macro::TokenStr
                     #(#field_name: self.#field_name.big_clone(),)*
                 }
             }
+            fn deep_copy_from(&mut self, other: &Self) {
+                #(self.#field_name.deep_copy_from(&other.#field_name);)*
+            }
+            fn verify(&self, verifier: &mut bigobject::internal::Verifier) {
+                #(self.#field_name.verify(verifier);)*
+            }
         }
     };
     proc_macro::TokenStream::from(expanded)
@@ -1,22 +1,103 @@
+pub mod bigbitmap;
+pub mod bigbytes;
+pub mod bigcountermap;
+pub mod bigheap;
+pub mod bigindexedmap;
+pub mod biglog;
 pub mod bigmap;
+pub mod bigmultimap;
+pub mod bigtimeseries;
+pub mod bigttlmap;
 pub mod bigvec;
 
-use std::any::Any;
+use std::{
+    any::Any,
+    ops::{Deref, DerefMut},
+};
 
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use crate::storage::{batch::Batch, prefix::Prefix};
+use crate::{
+    bigobject::bigmap::KeyRef,
+    storage::{batch::Batch, prefix::Prefix, verify::Verifier},
+};
+
+pub trait BigObject: Serialize + DeserializeOwned + Any + Send + Sync {
+    /// Whether values of this type keep secondary index entries next to their map.
+    #[doc(hidden)]
+    const INDEXED: bool = false;
 
-pub trait BigObject: Serialize + DeserializeOwned + Any {
     fn initialize<'a, F: FnOnce() -> &'a mut Prefix>(&mut self, prefix: F);
     fn finalize<'a, F: FnOnce() -> &'a mut Prefix>(&mut self, prefix: F, batch: &mut Batch);
     fn big_clone(&self) -> Self;
+    fn deep_copy_from(&mut self, other: &Self);
+    fn verify(&self, verifier: &mut Verifier);
+    /// Database keys of the index entries for this value stored under `key` in the map at
+    /// `prefix`.
+    #[doc(hidden)]
+    fn index_leaves<K: KeyRef>(&self, _prefix: &Prefix, _key: &K) -> Vec<Vec<u8>> {
+        Vec::new()
+    }
 }
 
-impl<T: Serialize + DeserializeOwned + Any + Clone> BigObject for T {
+impl<T: Serialize + DeserializeOwned + Any + Send + Sync + Clone> BigObject for T {
     fn initialize<'a, F: FnOnce() -> &'a mut Prefix>(&mut self, _prefix: F) {}
     fn finalize<'a, F: FnOnce() -> &'a mut Prefix>(&mut self, _prefix: F, _batch: &mut Batch) {}
     fn big_clone(&self) -> Self {
         self.clone()
     }
+    fn deep_copy_from(&mut self, other: &Self) {
+        *self = other.clone();
+    }
+    fn verify(&self, _verifier: &mut Verifier) {}
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct Ordered<T>(T);
+
+impl<T: BigObject> Ordered<T> {
+    fn check(prefix: &Prefix) {
+        assert!(
+            prefix.sorted(),
+            "BigHeap, BigTimeSeries and BigIndexedMap need ordered map keys and cannot use map-key \
+             encryption"
+        );
+    }
+}
+
+impl<T: BigObject> BigObject for Ordered<T> {
+    fn initialize<'a, F: FnOnce() -> &'a mut Prefix>(&mut self, prefix: F) {
+        let prefix = prefix();
+        Self::check(prefix);
+        self.0.initialize(|| prefix);
+    }
+    fn finalize<'a, F: FnOnce() -> &'a mut Prefix>(&mut self, prefix: F, batch: &mut Batch) {
+        let prefix = prefix();
+        Self::check(prefix);
+        self.0.finalize(|| prefix, batch);
+    }
+    fn big_clone(&self) -> Self {
+        Self(self.0.big_clone())
+    }
+    fn deep_copy_from(&mut self, other: &Self) {
+        self.0.deep_copy_from(&other.0);
+    }
+    fn verify(&self, verifier: &mut Verifier) {
+        self.0.verify(verifier);
+    }
+}
+
+impl<T> Deref for Ordered<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Ordered<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
 }
@@ -0,0 +1,159 @@
+use bigobject_derive::BigObject;
+use serde::{Deserialize, Serialize};
+
+use crate as bigobject;
+use crate::{bigobject::bigbytes::Chunk, BigMap};
+
+const CHUNK_BITS: u64 = 32 * 1024;
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+pub struct BigBitmap {
+    len: u64,
+    chunks: BigMap<u64, Chunk>,
+}
+
+type ChunkPairs<'a> = Box<dyn Iterator<Item = (u64, Option<&'a Chunk>, Option<&'a Chunk>)> + 'a>;
+
+fn split(id: u64) -> (u64, usize, u8) {
+    let bit = id % CHUNK_BITS;
+    (id / CHUNK_BITS, (bit / 8) as usize, 1 << (bit % 8))
+}
+
+fn ids(index: u64, bytes: Vec<u8>) -> impl Iterator<Item = u64> {
+    bytes
+        .into_iter()
+        .enumerate()
+        .flat_map(move |(offset, byte)| {
+            (0..8)
+                .filter(move |bit| byte & (1 << bit) != 0)
+                .map(move |bit| index * CHUNK_BITS + offset as u64 * 8 + bit)
+        })
+}
+
+fn combine(left: Option<&Chunk>, right: Option<&Chunk>, op: fn(u8, u8) -> u8) -> Vec<u8> {
+    let left = left.map_or(&[][..], |chunk| &chunk.0);
+    let right = right.map_or(&[][..], |chunk| &chunk.0);
+    (0..left.len().max(right.len()))
+        .map(|i| {
+            op(
+                left.get(i).copied().unwrap_or(0),
+                right.get(i).copied().unwrap_or(0),
+            )
+        })
+        .collect()
+}
+
+impl BigBitmap {
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn count(&self) -> u64 {
+        self.len
+    }
+    pub fn contains(&self, id: u64) -> bool {
+        let (index, offset, mask) = split(id);
+        self.chunks
+            .get(&index)
+            .and_then(|chunk| chunk.0.get(offset))
+            .is_some_and(|byte| byte & mask != 0)
+    }
+    pub fn set(&mut self, id: u64) -> bool {
+        if self.contains(id) {
+            return false;
+        }
+        let (index, offset, mask) = split(id);
+        if self.chunks.get(&index).is_none() {
+            self.chunks.insert(index, Chunk::default());
+        }
+        let chunk = &mut self.chunks.get_mut(&index).unwrap().0;
+        if chunk.len() <= offset {
+            chunk.resize(offset + 1, 0);
+        }
+        chunk[offset] |= mask;
+        self.len += 1;
+        true
+    }
+    pub fn unset(&mut self, id: u64) -> bool {
+        if !self.contains(id) {
+            return false;
+        }
+        let (index, offset, mask) = split(id);
+        let chunk = &mut self.chunks.get_mut(&index).unwrap().0;
+        chunk[offset] &= !mask;
+        while chunk.last() == Some(&0) {
+            chunk.pop();
+        }
+        if chunk.is_empty() {
+            self.chunks.remove(&index);
+        }
+        self.len -= 1;
+        true
+    }
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        self.chunks
+            .iter()
+            .flat_map(|(index, chunk)| ids(index, chunk.0.clone()))
+    }
+    pub fn and<'a>(&'a self, other: &'a BigBitmap) -> impl Iterator<Item = u64> + 'a {
+        self.chunks.iter().flat_map(|(index, chunk)| {
+            ids(
+                index,
+                combine(Some(chunk), other.chunks.get(&index), |a, b| a & b),
+            )
+        })
+    }
+    pub fn and_not<'a>(&'a self, other: &'a BigBitmap) -> impl Iterator<Item = u64> + 'a {
+        self.chunks.iter().flat_map(|(index, chunk)| {
+            ids(
+                index,
+                combine(Some(chunk), other.chunks.get(&index), |a, b| a & !b),
+            )
+        })
+    }
+    pub fn or<'a>(&'a self, other: &'a BigBitmap) -> impl Iterator<Item = u64> + 'a {
+        self.chunk_pairs(other)
+            .flat_map(|(index, left, right)| ids(index, combine(left, right, |a, b| a | b)))
+    }
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.len = 0;
+    }
+    fn chunk_pairs<'a>(&'a self, other: &'a BigBitmap) -> ChunkPairs<'a> {
+        if !self.chunks.is_sorted() || !other.chunks.is_sorted() {
+            let left = self
+                .chunks
+                .iter()
+                .map(|(index, chunk)| (index, Some(chunk), other.chunks.get(&index)));
+            let right = other
+                .chunks
+                .iter()
+                .filter(|(index, _)| self.chunks.get(index).is_none())
+                .map(|(index, chunk)| (index, None, Some(chunk)));
+            return Box::new(left.chain(right));
+        }
+        let mut left = self.chunks.iter().peekable();
+        let mut right = other.chunks.iter().peekable();
+        Box::new(std::iter::from_fn(move || {
+            match (left.peek(), right.peek()) {
+                (None, None) => None,
+                (Some((l, _)), Some((r, _))) if l == r => {
+                    let (index, l) = left.next().unwrap();
+                    let (_, r) = right.next().unwrap();
+                    Some((index, Some(l), Some(r)))
+                }
+                (Some((l, _)), Some((r, _))) if l > r => {
+                    let (index, r) = right.next().unwrap();
+                    Some((index, None, Some(r)))
+                }
+                (None, Some(_)) => {
+                    let (index, r) = right.next().unwrap();
+                    Some((index, None, Some(r)))
+                }
+                _ => {
+                    let (index, l) = left.next().unwrap();
+                    Some((index, Some(l), None))
+                }
+            }
+        }))
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    fmt,
+    io::{self, Read, Seek, SeekFrom},
+};
+
+use bigobject_derive::BigObject;
+use serde::{
+    de::{SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate as bigobject;
+use crate::BigMap;
+
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+#[derive(Clone, Default)]
+pub(crate) struct Chunk(pub(crate) Vec<u8>);
+
+impl Serialize for Chunk {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'a> Deserialize<'a> for Chunk {
+    fn deserialize<D: Deserializer<'a>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_byte_buf(ChunkVisitor).map(Chunk)
+    }
+}
+
+struct ChunkVisitor;
+
+impl<'a> Visitor<'a> for ChunkVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("bytes")
+    }
+    fn visit_bytes<E>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(bytes.to_vec())
+    }
+    fn visit_byte_buf<E>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(bytes)
+    }
+    fn visit_seq<A: SeqAccess<'a>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes = Vec::new();
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
+#[derive(Default, BigObject)]
+pub struct BigBytes {
+    len: u64,
+    chunks: BigMap<u64, Chunk>,
+}
+
+impl Serialize for BigBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_seq((0..self.len.div_ceil(CHUNK_SIZE)).flat_map(|index| {
+                let offset = index * CHUNK_SIZE;
+                let mut chunk = vec![0; (self.len - offset).min(CHUNK_SIZE) as usize];
+                self.read_at(offset, &mut chunk);
+                chunk
+            }))
+        } else {
+            self.len.serialize(serializer)
+        }
+    }
+}
+
+impl<'a> Deserialize<'a> for BigBytes {
+    fn deserialize<D: Deserializer<'a>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_seq(BytesVisitor)
+        } else {
+            Ok(Self {
+                len: u64::deserialize(deserializer)?,
+                chunks: BigMap::default(),
+            })
+        }
+    }
+}
+
+struct BytesVisitor;
+
+impl<'a> Visitor<'a> for BytesVisitor {
+    type Value = BigBytes;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("bytes")
+    }
+    fn visit_bytes<E>(self, data: &[u8]) -> Result<BigBytes, E> {
+        let mut bytes = BigBytes::importing();
+        bytes.append(data);
+        Ok(bytes)
+    }
+    fn visit_seq<A: SeqAccess<'a>>(self, mut seq: A) -> Result<BigBytes, A::Error> {
+        let mut bytes = BigBytes::importing();
+        let mut chunk = Vec::new();
+        while let Some(byte) = seq.next_element()? {
+            chunk.push(byte);
+            if chunk.len() as u64 == CHUNK_SIZE {
+                bytes.append(&chunk);
+                chunk.clear();
+            }
+        }
+        bytes.append(&chunk);
+        Ok(bytes)
+    }
+}
+
+impl BigBytes {
+    fn importing() -> Self {
+        Self {
+            len: 0,
+            chunks: BigMap::importing(),
+        }
+    }
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> usize {
+        let end = self.len.min(offset.saturating_add(buf.len() as u64));
+        let mut pos = offset;
+        while pos < end {
+            let start = (pos % CHUNK_SIZE) as usize;
+            let count = (CHUNK_SIZE - pos % CHUNK_SIZE).min(end - pos) as usize;
+            let out = &mut buf[(pos - offset) as usize..][..count];
+            let stored = self
+                .chunks
+                .get(&(pos / CHUNK_SIZE))
+                .map_or(&[][..], |chunk| chunk.0.get(start..).unwrap_or_default());
+            let stored = &stored[..stored.len().min(count)];
+            out[..stored.len()].copy_from_slice(stored);
+            out[stored.len()..].fill(0);
+            pos += count as u64;
+        }
+        end.saturating_sub(offset) as usize
+    }
+    pub fn write_at(&mut self, offset: u64, data: &[u8]) {
+        let end = offset + data.len() as u64;
+        let mut pos = offset;
+        while pos < end {
+            let index = pos / CHUNK_SIZE;
+            let start = (pos % CHUNK_SIZE) as usize;
+            let count = (CHUNK_SIZE - pos % CHUNK_SIZE).min(end - pos) as usize;
+            if self.chunks.get(&index).is_none() {
+                self.chunks.insert(index, Chunk::default());
+            }
+            let chunk = &mut self.chunks.get_mut(&index).unwrap().0;
+            if chunk.len() < start + count {
+                chunk.resize(start + count, 0);
+            }
+            chunk[start..start + count].copy_from_slice(&data[(pos - offset) as usize..][..count]);
+            pos += count as u64;
+        }
+        self.len = self.len.max(end);
+    }
+    pub fn append(&mut self, data: &[u8]) {
+        self.write_at(self.len, data);
+    }
+    pub fn truncate(&mut self, len: u64) {
+        if len >= self.len {
+            self.len = len;
+            return;
+        }
+        let kept_chunks = len.div_ceil(CHUNK_SIZE);
+        (kept_chunks..self.len.div_ceil(CHUNK_SIZE))
+            .map(|index| self.chunks.remove(&index))
+            .count();
+        let tail = (len % CHUNK_SIZE) as usize;
+        if tail != 0 {
+            if let Some(chunk) = self.chunks.get_mut(&(len / CHUNK_SIZE)) {
+                chunk.0.truncate(tail);
+            }
+        }
+        self.len = len;
+    }
+    pub fn reader(&self) -> Reader<'_> {
+        Reader {
+            bytes: self,
+            pos: 0,
+        }
+    }
+}
+
+pub struct Reader<'a> {
+    bytes: &'a BigBytes,
+    pos: u64,
+}
+
+impl<'a> Read for Reader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.bytes.read_at(self.pos, buf);
+        self.pos += count as u64;
+        Ok(count)
+    }
+}
+
+impl<'a> Seek for Reader<'a> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(delta) => self.bytes.len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+        };
+        self.pos = pos.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative position",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
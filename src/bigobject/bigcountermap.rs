@@ -0,0 +1,271 @@
+use std::{
+    any::Any,
+    borrow::Borrow,
+    cmp::Ordering,
+    collections::{btree_map, BTreeMap, BTreeSet},
+    fmt,
+    iter::Peekable,
+    marker::PhantomData,
+    mem::take,
+};
+
+use serde::{
+    de::{DeserializeOwned, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    bigobject::{
+        bigmap::{Key, KeyRef},
+        BigObject,
+    },
+    storage::{
+        batch::Batch,
+        json,
+        lock_context::{LockContext, RawIter},
+        prefix::Prefix,
+        staging::SPILL_CHANGES,
+        verify::Verifier,
+    },
+};
+
+pub struct BigCounterMap<K: Key> {
+    prefix: Option<Prefix>,
+    deltas: BTreeMap<K, i64>,
+    copying: bool,
+}
+
+impl<K: Key> Default for BigCounterMap<K> {
+    fn default() -> Self {
+        Self {
+            prefix: None,
+            deltas: BTreeMap::new(),
+            copying: false,
+        }
+    }
+}
+
+impl<K: Key> Serialize for BigCounterMap<K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_seq(self.iter())
+        } else {
+            serializer.serialize_unit()
+        }
+    }
+}
+
+impl<'a, K: Key> Deserialize<'a> for BigCounterMap<K> {
+    fn deserialize<D: Deserializer<'a>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_seq(CountersVisitor(PhantomData))
+        } else {
+            <()>::deserialize(deserializer)?;
+            Ok(Self::default())
+        }
+    }
+}
+
+struct CountersVisitor<K>(PhantomData<K>);
+
+impl<'a, K: Key> Visitor<'a> for CountersVisitor<K> {
+    type Value = BigCounterMap<K>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of key-counter pairs")
+    }
+    fn visit_seq<A: SeqAccess<'a>>(self, mut seq: A) -> Result<BigCounterMap<K>, A::Error> {
+        let mut counters = BigCounterMap {
+            prefix: json::import_prefix(),
+            ..Default::default()
+        };
+        while let Some((key, value)) = seq.next_element()? {
+            counters.add(key, value);
+        }
+        Ok(counters)
+    }
+}
+
+impl<K> BigObject for BigCounterMap<K>
+where
+    Self: Serialize + DeserializeOwned + Any,
+    K: Key,
+{
+    fn initialize<'a, F: FnOnce() -> &'a mut Prefix>(&mut self, prefix: F) {
+        self.prefix = Some(prefix().clone());
+    }
+
+    fn finalize<'a, F: FnOnce() -> &'a mut Prefix>(&mut self, prefix: F, batch: &mut Batch) {
+        let target = prefix();
+        let copying = take(&mut self.copying);
+        let prefix = match self.prefix.take() {
+            Some(source) if copying || !target.is_location_of(&source) => {
+                batch.delete_prefix(target);
+                batch.copy_prefix(&source, target);
+                target.without_relocation()
+            }
+            Some(_) => target.without_relocation(),
+            None => {
+                batch.delete_prefix(target);
+                target.without_relocation()
+            }
+        };
+        for (key, delta) in take(&mut self.deltas).into_iter() {
+            batch.merge(&prefix, &key, delta);
+        }
+        self.prefix = Some(prefix);
+    }
+    fn big_clone(&self) -> Self {
+        assert!(self.deltas.is_empty() && !self.copying);
+        Self {
+            prefix: self.prefix.as_ref().map(|prefix| prefix.clone()),
+            deltas: BTreeMap::new(),
+            copying: false,
+        }
+    }
+    fn deep_copy_from(&mut self, other: &Self) {
+        BigCounterMap::deep_copy_from(self, other);
+    }
+    fn verify(&self, verifier: &mut Verifier) {
+        if let Some(prefix) = &self.prefix {
+            verifier.visit_map::<K, i64>(prefix);
+        }
+    }
+}
+
+impl<K: Key> BigCounterMap<K> {
+    pub fn get<Q>(&self, key: &Q) -> i64
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized,
+    {
+        let stored = self
+            .prefix
+            .as_ref()
+            .and_then(|prefix| LockContext::get::<i64, _>(prefix, &key))
+            .copied()
+            .unwrap_or(0);
+        stored.wrapping_add(self.deltas.get(key).copied().unwrap_or(0))
+    }
+    pub fn add(&mut self, key: K, delta: i64) {
+        if let Some(staging) = self
+            .prefix
+            .as_ref()
+            .filter(|_| !self.copying)
+            .and_then(|prefix| prefix.staging.as_ref())
+        {
+            if self.deltas.len() >= SPILL_CHANGES {
+                let mut batch = Batch::spilling(staging.clone());
+                for (key, delta) in take(&mut self.deltas) {
+                    batch.merge(self.prefix.as_ref().unwrap(), &key, delta);
+                }
+            }
+        }
+        let value = self.deltas.entry(key).or_insert(0);
+        *value = value.wrapping_add(delta);
+    }
+    pub fn iter(&self) -> Iter<'_, K> {
+        Iter {
+            deltas: self.deltas.iter().peekable(),
+            stored: self
+                .prefix
+                .as_ref()
+                .map(|prefix| LockContext::raw_iter(prefix, prefix.leaf_range()).peekable()),
+            prefix: self.prefix.as_ref(),
+            all_deltas: &self.deltas,
+            seen: BTreeSet::new(),
+        }
+    }
+    pub fn clear(&mut self) {
+        self.deltas = BTreeMap::new();
+        if let Some(prefix) = self.prefix.as_ref().filter(|_| !self.copying) {
+            if let Some(staging) = &prefix.staging {
+                Batch::spilling(staging.clone()).delete_prefix(prefix);
+                return;
+            }
+        }
+        self.prefix = None;
+        self.copying = false;
+    }
+    pub fn deep_copy_from(&mut self, other: &Self) {
+        assert!(
+            other.deltas.is_empty(),
+            "Commit changes before copying a BigCounterMap"
+        );
+        self.clear();
+        if let Some(prefix) = &other.prefix {
+            self.prefix = Some(prefix.without_relocation());
+            self.copying = true;
+        }
+    }
+}
+
+pub struct Iter<'a, K: Key> {
+    deltas: Peekable<btree_map::Iter<'a, K, i64>>,
+    stored: Option<Peekable<RawIter>>,
+    prefix: Option<&'a Prefix>,
+    all_deltas: &'a BTreeMap<K, i64>,
+    seen: BTreeSet<K>,
+}
+
+impl<'a, K: Key> Iter<'a, K> {
+    fn decode_key(&self, db_key: &[u8]) -> K {
+        let prefix = self.prefix.unwrap();
+        prefix
+            .db()
+            .codec
+            .decode_map_key(Prefix::split_leaf(db_key).unwrap().1)
+            .unwrap()
+    }
+    fn next_stored(&mut self) -> Option<(K, i64)> {
+        let (db_key, encoded) = self.stored.as_mut()?.next()?;
+        let value = self.prefix.unwrap().db().codec.decode_value(&encoded);
+        Some((
+            self.decode_key(&db_key),
+            rmp_serde::from_slice(&value).unwrap(),
+        ))
+    }
+    fn next_unsorted(&mut self) -> Option<(K, i64)> {
+        if let Some((key, value)) = self.next_stored() {
+            let delta = self.all_deltas.get(&key).copied();
+            if delta.is_some() {
+                self.seen.insert(key.clone());
+            }
+            return Some((key, value.wrapping_add(delta.unwrap_or(0))));
+        }
+        let seen = &self.seen;
+        self.deltas
+            .find(|(key, _)| !seen.contains(*key))
+            .map(|(key, delta)| (key.clone(), *delta))
+    }
+}
+
+impl<'a, K: Key> Iterator for Iter<'a, K> {
+    type Item = (K, i64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.prefix.is_none_or(Prefix::sorted) {
+            return self.next_unsorted();
+        }
+        let stored_key = self
+            .stored
+            .as_mut()
+            .and_then(|stored| stored.peek())
+            .map(|(db_key, _)| db_key.clone());
+        let stored_key = stored_key.map(|db_key| self.decode_key(&db_key));
+        match (self.deltas.peek(), stored_key) {
+            (None, None) => None,
+            (None, Some(_)) => self.next_stored(),
+            (Some(_), None) => self.deltas.next().map(|(key, delta)| (key.clone(), *delta)),
+            (Some((key, _)), Some(stored_key)) => match (*key).cmp(&stored_key) {
+                Ordering::Less => self.deltas.next().map(|(key, delta)| (key.clone(), *delta)),
+                Ordering::Equal => {
+                    let (key, value) = self.next_stored().unwrap();
+                    let delta = self.deltas.next().unwrap().1;
+                    Some((key, value.wrapping_add(*delta)))
+                }
+                Ordering::Greater => self.next_stored(),
+            },
+        }
+    }
+}
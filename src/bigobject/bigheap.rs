@@ -0,0 +1,73 @@
+use bigobject_derive::BigObject;
+use serde::{Deserialize, Serialize};
+
+use crate as bigobject;
+use crate::{
+    bigobject::{bigmap::Key, BigObject, Ordered},
+    BigMap,
+};
+
+#[derive(BigObject, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BigHeap<P: Key, T: BigObject> {
+    len: u64,
+    next_seq: u64,
+    entries: Ordered<BigMap<(P, u64), T>>,
+    head: Option<(P, u64)>,
+}
+
+impl<P: Key, T: BigObject> Default for BigHeap<P, T> {
+    fn default() -> Self {
+        Self {
+            len: 0,
+            next_seq: 0,
+            entries: Ordered::default(),
+            head: None,
+        }
+    }
+}
+
+impl<P: Key, T: BigObject> BigHeap<P, T> {
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn push(&mut self, priority: P, value: T) {
+        let key = (priority, self.next_seq);
+        if self.head.as_ref().is_some_and(|head| key < *head) {
+            self.head = Some(key.clone());
+        }
+        self.entries.insert(key, value);
+        self.next_seq += 1;
+        self.len += 1;
+    }
+    pub fn peek(&self) -> Option<(P, &T)> {
+        self.first().map(|((priority, _), value)| (priority, value))
+    }
+    pub fn pop(&mut self) -> Option<(P, T)> {
+        let (key, _) = self.first()?;
+        let value = self.entries.take(&key).unwrap();
+        self.len -= 1;
+        let priority = key.0.clone();
+        self.head = Some(key);
+        Some((priority, value))
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (P, &T)> + '_ {
+        self.entries
+            .iter()
+            .map(|((priority, _), value)| (priority, value))
+    }
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.len = 0;
+        self.head = None;
+    }
+    fn first(&self) -> Option<((P, u64), &T)> {
+        match &self.head {
+            Some(head) => self.entries.range(head.clone()..).next(),
+            None => self.entries.iter().next(),
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::{
+    borrow::Borrow,
+    iter::Peekable,
+    marker::PhantomData,
+    ops::{Bound, Deref, RangeBounds},
+};
+
+use bigobject_derive::BigObject;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate as bigobject;
+use crate::{
+    bigobject::{
+        bigmap::{Key, KeyRef},
+        BigObject, Ordered,
+    },
+    storage::{batch::Batch, lock_context::LockContext, prefix::Prefix, verify::Verifier},
+    BigMap,
+};
+
+/// A secondary index of a `BigIndexedMap`, named so entries survive reordering the indexes.
+pub struct MapIndex<V, IK> {
+    name: &'static str,
+    extract: fn(&V) -> IK,
+}
+
+impl<V, IK> MapIndex<V, IK> {
+    pub const fn new(name: &'static str, extract: fn(&V) -> IK) -> Self {
+        Self { name, extract }
+    }
+}
+
+pub trait AnyMapIndex<V>: Sync {
+    fn entry_prefix(&self, value: &V) -> Vec<u8>;
+}
+
+impl<V, IK: Key> AnyMapIndex<V> for MapIndex<V, IK> {
+    fn entry_prefix(&self, value: &V) -> Vec<u8> {
+        storekey::serialize(&(self.name, (self.extract)(value))).unwrap()
+    }
+}
+
+pub trait MapIndexes<V: 'static>: Send + Sync + 'static {
+    const INDEXES: &'static [&'static dyn AnyMapIndex<V>];
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(transparent, bound = "")]
+struct Indexed<V: BigObject, I: MapIndexes<V>> {
+    value: V,
+    #[serde(skip)]
+    _indexes: PhantomData<I>,
+}
+
+impl<V: BigObject, I: MapIndexes<V>> Deref for Indexed<V, I> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        &self.value
+    }
+}
+
+impl<V: BigObject, I: MapIndexes<V>> BigObject for Indexed<V, I> {
+    const INDEXED: bool = true;
+
+    fn initialize<'a, F: FnOnce() -> &'a mut Prefix>(&mut self, prefix: F) {
+        self.value.initialize(prefix);
+    }
+    fn finalize<'a, F: FnOnce() -> &'a mut Prefix>(&mut self, prefix: F, batch: &mut Batch) {
+        self.value.finalize(prefix, batch);
+    }
+    fn big_clone(&self) -> Self {
+        Self {
+            value: self.value.big_clone(),
+            _indexes: PhantomData,
+        }
+    }
+    fn deep_copy_from(&mut self, other: &Self) {
+        self.value.deep_copy_from(&other.value);
+    }
+    fn verify(&self, verifier: &mut Verifier) {
+        self.value.verify(verifier);
+    }
+    fn index_leaves<K: KeyRef>(&self, prefix: &Prefix, key: &K) -> Vec<Vec<u8>> {
+        let index = index_prefix(prefix);
+        let map_key = storekey::serialize(key).unwrap();
+        I::INDEXES
+            .iter()
+            .map(|index_def| {
+                index.stored_leaf(&[index_def.entry_prefix(&self.value), map_key.clone()].concat())
+            })
+            .collect()
+    }
+}
+
+/// Index entries sit in the field before the data, so clearing or relocating them happens
+/// before `Batch::put` writes the new entries.
+fn index_prefix(data: &Prefix) -> Prefix {
+    let mut index = data.without_relocation();
+    index.set_field_index(0);
+    index
+}
+
+/// Index entries are keyed by `(index name, index key, map key)` and are written by
+/// `Batch::put` and `Batch::delete` for the data map next to them.
+struct IndexEntries<K: Key, V: BigObject, I: MapIndexes<V>> {
+    entries: Ordered<BigMap<Vec<u8>, ()>>,
+    _data: PhantomData<(K, V, I)>,
+}
+
+impl<K: Key, V: BigObject, I: MapIndexes<V>> Default for IndexEntries<K, V, I> {
+    fn default() -> Self {
+        Self {
+            entries: Ordered::default(),
+            _data: PhantomData,
+        }
+    }
+}
+
+impl<K: Key, V: BigObject, I: MapIndexes<V>> Serialize for IndexEntries<K, V, I> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_unit()
+    }
+}
+
+impl<'a, K: Key, V: BigObject, I: MapIndexes<V>> Deserialize<'a> for IndexEntries<K, V, I> {
+    fn deserialize<D: Deserializer<'a>>(deserializer: D) -> Result<Self, D::Error> {
+        <()>::deserialize(deserializer)?;
+        Ok(Self::default())
+    }
+}
+
+impl<K: Key, V: BigObject, I: MapIndexes<V>> BigObject for IndexEntries<K, V, I> {
+    fn initialize<'a, F: FnOnce() -> &'a mut Prefix>(&mut self, prefix: F) {
+        self.entries.initialize(prefix);
+    }
+    fn finalize<'a, F: FnOnce() -> &'a mut Prefix>(&mut self, prefix: F, batch: &mut Batch) {
+        self.entries.finalize(prefix, batch);
+    }
+    fn big_clone(&self) -> Self {
+        Self {
+            entries: self.entries.big_clone(),
+            _data: PhantomData,
+        }
+    }
+    fn deep_copy_from(&mut self, other: &Self) {
+        self.entries.deep_copy_from(&other.entries);
+    }
+    fn verify(&self, verifier: &mut Verifier) {
+        let Some(prefix) = self.entries.prefix() else {
+            return;
+        };
+        verifier.visit_index(prefix);
+        let mut data = prefix.clone();
+        data.set_field_index(1);
+        let mut expected = 0;
+        let mut consistent = true;
+        for (key, value) in LockContext::iter::<K, Indexed<V, I>>(&data, None) {
+            for leaf in value.index_leaves(&data, &key) {
+                expected += 1;
+                consistent &= LockContext::raw_iter(prefix, (leaf.clone(), None))
+                    .next()
+                    .is_some_and(|(db_key, _)| *db_key == *leaf);
+            }
+        }
+        let stored = LockContext::raw_iter(prefix, prefix.leaf_range()).count();
+        if !consistent || stored != expected {
+            verifier.report_stale_index(prefix);
+        }
+    }
+}
+
+#[derive(BigObject, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BigIndexedMap<K: Key, V: BigObject, I: MapIndexes<V>> {
+    #[serde(skip)]
+    index: IndexEntries<K, V, I>,
+    data: BigMap<K, Indexed<V, I>>,
+}
+
+impl<K: Key, V: BigObject, I: MapIndexes<V>> Default for BigIndexedMap<K, V, I> {
+    fn default() -> Self {
+        Self {
+            index: IndexEntries::default(),
+            data: BigMap::default(),
+        }
+    }
+}
+
+impl<K: Key, V: BigObject, I: MapIndexes<V>> BigIndexedMap<K, V, I> {
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized,
+    {
+        self.data.get(key).map(Deref::deref)
+    }
+    pub async fn get_async<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized,
+    {
+        self.data.get_async(key).await.map(Deref::deref)
+    }
+    pub fn insert(&mut self, key: K, value: V) {
+        self.data.insert(
+            key,
+            Indexed {
+                value,
+                _indexes: PhantomData,
+            },
+        );
+    }
+    pub fn remove<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized + ToOwned<Owned = K>,
+    {
+        self.data.remove(key);
+    }
+    pub fn update<Q, R>(&mut self, key: &Q, f: impl FnOnce(&mut V) -> R) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized + ToOwned<Owned = K>,
+    {
+        self.data.get_mut(key).map(|indexed| f(&mut indexed.value))
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> + '_ {
+        self.data.iter().map(|(key, value)| (key, &**value))
+    }
+    pub fn get_by_index<IK: Key>(
+        &self,
+        index: &MapIndex<V, IK>,
+        index_key: &IK,
+    ) -> impl Iterator<Item = (K, &V)> + '_ {
+        self.range_by_index(index, index_key.clone()..=index_key.clone())
+    }
+    /// Entries ordered by index key, then map key, including changes not committed yet.
+    pub fn range_by_index<IK: Key, R: RangeBounds<IK>>(
+        &self,
+        index: &MapIndex<V, IK>,
+        range: R,
+    ) -> impl Iterator<Item = (K, &V)> + '_ {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+        let name = index.name;
+        let mut changed: Vec<(IK, K)> = self
+            .data
+            .changes()
+            .iter()
+            .filter_map(|(key, value)| Some(((index.extract)(value.as_ref()?), key.clone())))
+            .filter(|(index_key, _)| range.contains(index_key))
+            .collect();
+        changed.sort();
+        let stored = self.index.entries.prefix().map(|prefix| {
+            let start = match &range.0 {
+                Bound::Included(index_key) | Bound::Excluded(index_key) => {
+                    storekey::serialize(&(name, index_key)).unwrap()
+                }
+                Bound::Unbounded => storekey::serialize(&name).unwrap(),
+            };
+            let (mut from, to) = prefix.leaf_range();
+            from.truncate(prefix.len() + 1);
+            from.extend_from_slice(&start);
+            LockContext::raw_iter(prefix, (from, to))
+        });
+        let name_prefix = storekey::serialize(&name).unwrap();
+        let end = range.1.clone();
+        let stored = stored
+            .into_iter()
+            .flatten()
+            .map_while(move |(db_key, _)| {
+                let (_, map_key) = Prefix::split_leaf(&db_key)?;
+                if !map_key.starts_with(&name_prefix) {
+                    return None;
+                }
+                let (_, index_key, key) =
+                    storekey::deserialize::<(String, IK, K)>(map_key).unwrap();
+                (Bound::Unbounded, end.as_ref())
+                    .contains(&index_key)
+                    .then_some((index_key, key))
+            })
+            .filter(move |(index_key, key)| {
+                range.contains(index_key) && !self.data.changes().contains_key(key)
+            });
+        MergeSorted {
+            left: stored.peekable(),
+            right: changed.into_iter().peekable(),
+        }
+        .map(|(_, key)| {
+            let value = self.data.get(&key).unwrap();
+            (key, &**value)
+        })
+    }
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.index.entries.clear();
+    }
+}
+
+struct MergeSorted<T, L: Iterator<Item = T>, R: Iterator<Item = T>> {
+    left: Peekable<L>,
+    right: Peekable<R>,
+}
+
+impl<T: Ord, L: Iterator<Item = T>, R: Iterator<Item = T>> Iterator for MergeSorted<T, L, R> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match (self.left.peek(), self.right.peek()) {
+            (Some(left), Some(right)) if right < left => self.right.next(),
+            (Some(_), _) => self.left.next(),
+            (None, _) => self.right.next(),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use bigobject_derive::BigObject;
+use serde::{Deserialize, Serialize};
+
+use crate as bigobject;
+use crate::{bigobject::BigObject, BigMap};
+
+const SEGMENT_LEN: u64 = 4096;
+
+#[derive(BigObject, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BigLog<T: BigObject> {
+    start: u64,
+    end: u64,
+    segments: BigMap<u64, BigMap<u64, T>>,
+    consumers: BigMap<String, u64>,
+}
+
+impl<T: BigObject> Default for BigLog<T> {
+    fn default() -> Self {
+        Self {
+            start: 0,
+            end: 0,
+            segments: BigMap::default(),
+            consumers: BigMap::default(),
+        }
+    }
+}
+
+impl<T: BigObject> BigLog<T> {
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+    pub fn append(&mut self, value: T) -> u64 {
+        let offset = self.end;
+        let segment = offset / SEGMENT_LEN;
+        if self.segments.get(&segment).is_none() {
+            self.segments.insert(segment, BigMap::default());
+        }
+        self.segments[&segment].insert(offset, value);
+        self.end += 1;
+        offset
+    }
+    pub fn get(&self, offset: u64) -> Option<&T> {
+        if offset < self.start || offset >= self.end {
+            return None;
+        }
+        self.segments[&(offset / SEGMENT_LEN)].get(&offset)
+    }
+    pub fn read_from(&self, offset: u64) -> impl Iterator<Item = (u64, &T)> + '_ {
+        let offset = offset.max(self.start);
+        self.segments
+            .range(offset / SEGMENT_LEN..)
+            .flat_map(move |(_, segment)| segment.range(offset..))
+    }
+    pub fn truncate_before(&mut self, offset: u64) -> u64 {
+        let offset = offset.min(self.end);
+        if offset <= self.start {
+            return 0;
+        }
+        for segment in self.start / SEGMENT_LEN..offset / SEGMENT_LEN {
+            self.segments.remove(&segment);
+        }
+        let dropped = offset - self.start;
+        self.start = offset;
+        dropped
+    }
+    pub fn consumer_offset(&self, name: &str) -> Option<u64> {
+        self.consumers.get(name).copied()
+    }
+    pub fn set_consumer_offset(&mut self, name: &str, offset: u64) {
+        self.consumers.insert(name.to_string(), offset);
+    }
+    pub fn remove_consumer(&mut self, name: &str) {
+        self.consumers.remove(name);
+    }
+    pub fn consumers(&self) -> impl Iterator<Item = (String, u64)> + '_ {
+        self.consumers.iter().map(|(name, offset)| (name, *offset))
+    }
+}
@@ -1,57 +1,96 @@
 use std::{
     any::Any,
     borrow::Borrow,
-    collections::BTreeMap,
+    cmp::Ordering,
+    collections::{btree_map, BTreeMap},
+    fmt,
+    iter::Peekable,
+    marker::PhantomData,
     mem::take,
-    ops::{Index, IndexMut},
+    ops::{Bound, Index, IndexMut, RangeBounds},
+    sync::{Arc, Weak},
 };
 
-use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use serde::{
+    de::{DeserializeOwned, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
 use crate::{
     bigobject::BigObject,
     storage::{
         batch::Batch,
-        lock_context::{LockContext, PhantomContext},
+        bulk::BulkRun,
+        db::DbInner,
+        json,
+        lock_context::{LockContext, MapIter},
         prefix::Prefix,
+        staging::{Staging, SPILL_CHANGES},
+        verify::Verifier,
     },
 };
 
 pub trait KeyRef: Serialize + Ord {}
 impl<T: Serialize + Ord + ?Sized> KeyRef for T {}
 
-pub trait Key: Serialize + DeserializeOwned + Ord + Clone + 'static {}
-impl<T: Serialize + DeserializeOwned + Ord + Clone + 'static> Key for T {}
+pub trait Key: Serialize + DeserializeOwned + Ord + Clone + Send + Sync + 'static {}
+impl<T: Serialize + DeserializeOwned + Ord + Clone + Send + Sync + 'static> Key for T {}
 
 pub struct BigMap<K: Key, V: BigObject> {
     prefix: Option<Prefix>,
+    db: Weak<DbInner>,
     changes: BTreeMap<K, Option<V>>,
-    _phantom: PhantomContext,
+    bulk: Option<BulkRun>,
+    copying: bool,
 }
 
 impl<K: Key, V: BigObject> Default for BigMap<K, V> {
     fn default() -> Self {
         Self {
             prefix: None,
+            db: Weak::new(),
             changes: BTreeMap::new(),
-            _phantom: Default::default(),
+            bulk: None,
+            copying: false,
         }
     }
 }
 
 impl<K: Key, V: BigObject> Serialize for BigMap<K, V> {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        serializer.serialize_unit()
+        if serializer.is_human_readable() {
+            serializer.collect_seq(self.iter())
+        } else {
+            serializer.serialize_unit()
+        }
     }
 }
 
 impl<'a, K: Key, V: BigObject> Deserialize<'a> for BigMap<K, V> {
-    fn deserialize<D: Deserializer<'a>>(_deserializer: D) -> Result<Self, D::Error> {
-        Ok(Self {
-            prefix: None,
-            changes: BTreeMap::new(),
-            _phantom: Default::default(),
-        })
+    fn deserialize<D: Deserializer<'a>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_seq(EntriesVisitor(PhantomData))
+        } else {
+            <()>::deserialize(deserializer)?;
+            Ok(Self::default())
+        }
+    }
+}
+
+struct EntriesVisitor<K, V>(PhantomData<(K, V)>);
+
+impl<'a, K: Key, V: BigObject> Visitor<'a> for EntriesVisitor<K, V> {
+    type Value = BigMap<K, V>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of key-value pairs")
+    }
+    fn visit_seq<A: SeqAccess<'a>>(self, mut seq: A) -> Result<BigMap<K, V>, A::Error> {
+        let mut map = BigMap::importing();
+        while let Some((key, value)) = seq.next_element()? {
+            map.insert(key, value);
+        }
+        Ok(map)
     }
 }
 
@@ -62,29 +101,86 @@ where
     V: BigObject,
 {
     fn initialize<'a, F: FnOnce() -> &'a mut Prefix>(&mut self, prefix: F) {
-        self.prefix = Some(prefix().clone());
+        let prefix = prefix();
+        self.db = prefix.db.clone();
+        self.prefix = Some(prefix.clone());
     }
 
     fn finalize<'a, F: FnOnce() -> &'a mut Prefix>(&mut self, prefix: F, batch: &mut Batch) {
-        let prefix = self.prefix.get_or_insert_with(|| {
-            let prefix = prefix().clone();
-            batch.delete_prefix(&prefix);
-            prefix
-        });
-        for (key, value) in take(&mut self.changes).into_iter() {
-            if let Some(value) = value {
-                batch.put(prefix, &key, value);
-            } else {
-                batch.delete(prefix, &key);
+        let target = prefix();
+        let copying = take(&mut self.copying);
+        let bulk = self.bulk.take();
+        let prefix = match self.prefix.take() {
+            Some(source) if copying || !target.is_location_of(&source) => {
+                batch.delete_prefix(target);
+                let prefix = target.relocated_from(&source);
+                match &bulk {
+                    Some(run) => copy_absent(&source, run),
+                    None if V::INDEXED => copy_values::<K, V>(&source, &prefix, batch),
+                    None => batch.copy_prefix(&source, target),
+                }
+                prefix
             }
+            Some(_) => target.clone(),
+            None => {
+                batch.delete_prefix(target);
+                target.clone()
+            }
+        };
+        if let Some(run) = bulk {
+            batch.ingest(&prefix, run);
         }
+        write_changes(&prefix, take(&mut self.changes), batch);
+        self.db = prefix.db.clone();
+        self.prefix = Some(prefix.without_relocation());
     }
     fn big_clone(&self) -> Self {
-        assert!(self.changes.is_empty());
+        assert!(self.changes.is_empty() && self.bulk.is_none() && !self.copying);
         Self {
             prefix: self.prefix.as_ref().map(|prefix| prefix.clone()),
+            db: self.db.clone(),
             changes: BTreeMap::new(),
-            _phantom: Default::default(),
+            bulk: None,
+            copying: false,
+        }
+    }
+    fn deep_copy_from(&mut self, other: &Self) {
+        BigMap::deep_copy_from(self, other);
+    }
+    fn verify(&self, verifier: &mut Verifier) {
+        if let Some(prefix) = &self.prefix {
+            verifier.visit_map::<K, V>(prefix);
+        }
+    }
+}
+
+fn copy_absent(source: &Prefix, run: &BulkRun) {
+    for (db_key, encoded) in LockContext::raw_iter(source, source.leaf_range()) {
+        if let Some((prefix, map_key)) = Prefix::split_leaf(&db_key) {
+            if prefix == source.key {
+                run.put_if_absent(map_key, &encoded);
+            }
+        }
+    }
+}
+
+/// Copies values through `Batch::put`, which writes their index entries at the new location.
+fn copy_values<K: Key, V: BigObject>(source: &Prefix, target: &Prefix, batch: &mut Batch) {
+    for (key, value) in LockContext::iter::<K, V>(source, None) {
+        batch.put(target, &key, value.big_clone());
+    }
+}
+
+fn write_changes<K: Key, V: BigObject>(
+    prefix: &Prefix,
+    changes: BTreeMap<K, Option<V>>,
+    batch: &mut Batch,
+) {
+    for (key, value) in changes.into_iter() {
+        if let Some(value) = value {
+            batch.put(prefix, &key, value);
+        } else {
+            batch.delete::<V, K>(prefix, &key);
         }
     }
 }
@@ -128,11 +224,31 @@ impl<K: Key, V: BigObject> BigMap<K, V> {
             |value| value.as_ref(),
         )
     }
+    pub async fn get_async<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized,
+    {
+        match self.changes.get(key) {
+            Some(value) => value.as_ref(),
+            None => match &self.prefix {
+                Some(prefix) => LockContext::get_async(prefix, &key).await,
+                None => None,
+            },
+        }
+    }
     pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
     where
         K: Borrow<Q>,
         Q: KeyRef + ?Sized + ToOwned<Owned = K>,
     {
+        self.spill();
+        if let Some(staging) = self.staging() {
+            if let Some(Some(_)) = self.changes.get(key) {
+                let value = self.changes.remove(key).unwrap().unwrap();
+                Batch::spilling(staging).put(self.prefix.as_ref().unwrap(), &key, value);
+            }
+        }
         if !self.changes.contains_key(key) {
             self.changes.insert(
                 key.to_owned(),
@@ -144,6 +260,7 @@ impl<K: Key, V: BigObject> BigMap<K, V> {
         self.changes.get_mut(key).unwrap().as_mut()
     }
     pub fn insert(&mut self, key: K, value: V) {
+        self.spill();
         self.changes.insert(key, Some(value));
     }
     pub fn remove<Q>(&mut self, key: &Q)
@@ -151,6 +268,7 @@ impl<K: Key, V: BigObject> BigMap<K, V> {
         K: Borrow<Q>,
         Q: KeyRef + ?Sized + ToOwned<Owned = K>,
     {
+        self.spill();
         match self.changes.get_mut(key) {
             Some(value) => {
                 *value = None;
@@ -160,8 +278,210 @@ impl<K: Key, V: BigObject> BigMap<K, V> {
             }
         };
     }
+    pub(crate) fn take<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized + ToOwned<Owned = K>,
+    {
+        self.spill();
+        match self.changes.insert(key.to_owned(), None) {
+            Some(value) => value,
+            None => self
+                .prefix
+                .as_ref()
+                .and_then(|prefix| LockContext::get(prefix, &key).map(V::big_clone)),
+        }
+    }
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.range(..)
+    }
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Iter<'_, K, V> {
+        let start = range.start_bound().cloned();
+        let end = range.end_bound().cloned();
+        let sorted = self.is_sorted();
+        let from = match &start {
+            Bound::Included(key) | Bound::Excluded(key) if sorted => Some(key),
+            _ => None,
+        };
+        Iter {
+            changes: self.changes.range((start.clone(), end.clone())).peekable(),
+            stored: self
+                .prefix
+                .as_ref()
+                .map(|prefix| LockContext::iter(prefix, from).peekable()),
+            all_changes: &self.changes,
+            sorted,
+            start,
+            end,
+        }
+    }
+    pub(crate) fn prefix(&self) -> Option<&Prefix> {
+        self.prefix.as_ref()
+    }
+    pub(crate) fn changes(&self) -> &BTreeMap<K, Option<V>> {
+        &self.changes
+    }
+    pub(crate) fn is_sorted(&self) -> bool {
+        self.prefix.as_ref().is_none_or(Prefix::sorted)
+    }
     pub fn clear(&mut self) {
-        self.prefix = None;
+        self.bulk = None;
         self.changes = BTreeMap::new();
+        if let Some(staging) = self.staging() {
+            Batch::spilling(staging).delete_prefix(self.prefix.as_ref().unwrap());
+            return;
+        }
+        self.prefix = None;
+        self.copying = false;
+    }
+    /// `V: Clone` limits bulk loads to plain values, which hold no big objects to initialize.
+    /// The map must belong to a database, so a new nested map has to be committed first.
+    pub fn bulk_load<I: IntoIterator<Item = (K, V)>>(&mut self, entries: I)
+    where
+        V: Clone,
+    {
+        assert!(self.changes.is_empty() && !self.copying);
+        let db = self
+            .db
+            .upgrade()
+            .expect("bulk_load needs a map that belongs to a database");
+        let mut last_key = None;
+        let entries = entries.into_iter().inspect(|(key, _)| {
+            assert!(
+                last_key.as_ref() < Some(key),
+                "bulk_load keys must be sorted"
+            );
+            last_key = Some(key.clone());
+        });
+        if let Some(staging) = self.staging() {
+            let prefix = self.prefix.as_ref().unwrap();
+            let mut batch = Batch::spilling(staging);
+            for (key, value) in entries {
+                batch.put(prefix, &key, value);
+            }
+            return;
+        }
+        let run = self.bulk.get_or_insert_with(|| BulkRun::create(&db));
+        run.load(
+            &db,
+            entries.map(|(key, value)| {
+                let mut map_key = Vec::new();
+                db.codec.encode_map_key(&key, &mut map_key);
+                let encoded = db.codec.encode_value(rmp_serde::to_vec(&value).unwrap());
+                (map_key, encoded)
+            }),
+        );
+    }
+    /// An empty map that spills to the staging area of a running `Db::import_json`.
+    pub(crate) fn importing() -> Self {
+        match json::import_prefix() {
+            Some(prefix) => Self {
+                db: prefix.db.clone(),
+                prefix: Some(prefix),
+                ..Self::default()
+            },
+            None => Self::default(),
+        }
+    }
+    pub fn deep_copy_from(&mut self, other: &Self) {
+        assert!(
+            other.changes.is_empty() && other.bulk.is_none(),
+            "Commit changes before copying a BigMap"
+        );
+        self.clear();
+        if let Some(prefix) = &other.prefix {
+            self.db = prefix.db.clone();
+            self.prefix = Some(prefix.without_relocation());
+            self.copying = true;
+        }
+    }
+    fn staging(&self) -> Option<Arc<Staging>> {
+        self.prefix
+            .as_ref()
+            .filter(|_| !self.copying)
+            .and_then(|prefix| prefix.staging.clone())
+    }
+    fn spill(&mut self) {
+        if self.changes.len() < SPILL_CHANGES {
+            return;
+        }
+        if let Some(staging) = self.staging() {
+            let mut batch = Batch::spilling(staging);
+            write_changes(
+                self.prefix.as_ref().unwrap(),
+                take(&mut self.changes),
+                &mut batch,
+            );
+        }
+    }
+}
+
+pub struct Iter<'a, K: Key, V: BigObject> {
+    changes: Peekable<btree_map::Range<'a, K, Option<V>>>,
+    stored: Option<Peekable<MapIter<K, V>>>,
+    all_changes: &'a BTreeMap<K, Option<V>>,
+    sorted: bool,
+    start: Bound<K>,
+    end: Bound<K>,
+}
+
+impl<'a, K: Key, V: BigObject> Iter<'a, K, V> {
+    fn peek_stored(&mut self) -> Option<&K> {
+        while let Some((key, _)) = self.stored.as_mut()?.peek() {
+            if let Bound::Excluded(start) = &self.start {
+                if key == start {
+                    self.stored.as_mut().unwrap().next();
+                    continue;
+                }
+            }
+            if !(self.start.clone(), self.end.clone()).contains(key) {
+                self.stored = None;
+            }
+            break;
+        }
+        self.stored.as_mut()?.peek().map(|(key, _)| key)
+    }
+    fn next_unsorted(&mut self) -> Option<(K, &'a V)> {
+        let range = (self.start.clone(), self.end.clone());
+        let changes = self.all_changes;
+        self.stored
+            .as_mut()?
+            .find(|(key, _)| range.contains(key) && !changes.contains_key(key))
+    }
+}
+
+impl<'a, K: Key, V: BigObject> Iterator for Iter<'a, K, V> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.sorted {
+            if let Some(entry) = self.next_unsorted() {
+                return Some(entry);
+            }
+            self.stored = None;
+        }
+        loop {
+            let stored = self.peek_stored().cloned();
+            let take_change = match (self.changes.peek(), stored) {
+                (None, None) => return None,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (Some((key, _)), Some(stored_key)) => match (*key).cmp(&stored_key) {
+                    Ordering::Less => true,
+                    Ordering::Equal => {
+                        self.stored.as_mut().unwrap().next();
+                        true
+                    }
+                    Ordering::Greater => false,
+                },
+            };
+            if !take_change {
+                return self.stored.as_mut().unwrap().next();
+            }
+            let (key, value) = self.changes.next().unwrap();
+            if let Some(value) = value {
+                return Some((key.clone(), value));
+            }
+        }
     }
 }
@@ -0,0 +1,111 @@
+use std::borrow::Borrow;
+
+use bigobject_derive::BigObject;
+use serde::{Deserialize, Serialize};
+
+use crate as bigobject;
+use crate::{
+    bigobject::bigmap::{Key, KeyRef},
+    BigMap,
+};
+
+#[derive(BigObject, Serialize, Deserialize)]
+#[serde(bound = "")]
+struct Values<V: Key> {
+    len: u64,
+    values: BigMap<V, ()>,
+}
+
+impl<V: Key> Default for Values<V> {
+    fn default() -> Self {
+        Self {
+            len: 0,
+            values: BigMap::default(),
+        }
+    }
+}
+
+#[derive(BigObject, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BigMultiMap<K: Key, V: Key> {
+    entries: BigMap<K, Values<V>>,
+}
+
+impl<K: Key, V: Key> Default for BigMultiMap<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: BigMap::default(),
+        }
+    }
+}
+
+impl<K: Key, V: Key> BigMultiMap<K, V> {
+    pub fn insert(&mut self, key: K, value: V) -> bool {
+        if self.contains(&key, &value) {
+            return false;
+        }
+        if self.entries.get(&key).is_none() {
+            self.entries.insert(key.clone(), Values::default());
+        }
+        let entry = self.entries.get_mut(&key).unwrap();
+        entry.values.insert(value, ());
+        entry.len += 1;
+        true
+    }
+    pub fn remove<Q>(&mut self, key: &Q, value: &V) -> bool
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized + ToOwned<Owned = K>,
+    {
+        if !self.contains(key, value) {
+            return false;
+        }
+        let entry = self.entries.get_mut(key).unwrap();
+        if entry.len == 1 {
+            self.entries.remove(key);
+        } else {
+            entry.values.remove(value);
+            entry.len -= 1;
+        }
+        true
+    }
+    pub fn remove_all<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized + ToOwned<Owned = K>,
+    {
+        self.entries.remove(key);
+    }
+    pub fn contains<Q>(&self, key: &Q, value: &V) -> bool
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized,
+    {
+        self.entries
+            .get(key)
+            .is_some_and(|entry| entry.values.get(value).is_some())
+    }
+    pub fn count<Q>(&self, key: &Q) -> u64
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized,
+    {
+        self.entries.get(key).map_or(0, |entry| entry.len)
+    }
+    pub fn get_all<Q>(&self, key: &Q) -> impl Iterator<Item = V> + '_
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized,
+    {
+        self.entries
+            .get(key)
+            .into_iter()
+            .flat_map(|entry| entry.values.iter().map(|(value, _)| value))
+    }
+    pub fn keys(&self) -> impl Iterator<Item = K> + '_ {
+        self.entries.iter().map(|(key, _)| key)
+    }
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,156 @@
+use std::ops::{Bound, RangeBounds};
+
+use bigobject_derive::BigObject;
+use serde::{Deserialize, Serialize};
+
+use crate as bigobject;
+use crate::{
+    bigobject::{BigObject, Ordered},
+    BigMap,
+};
+
+const DEFAULT_WINDOW: u64 = 60 * 60 * 1000;
+
+#[derive(BigObject, Serialize, Deserialize)]
+#[serde(bound = "")]
+struct Window<T: BigObject> {
+    len: u64,
+    points: BigMap<(u64, u64), T>,
+}
+
+impl<T: BigObject> Default for Window<T> {
+    fn default() -> Self {
+        Self {
+            len: 0,
+            points: BigMap::default(),
+        }
+    }
+}
+
+#[derive(BigObject, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BigTimeSeries<T: BigObject> {
+    window: u64,
+    len: u64,
+    next_seq: u64,
+    windows: Ordered<BigMap<u64, Window<T>>>,
+}
+
+impl<T: BigObject> Default for BigTimeSeries<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW)
+    }
+}
+
+impl<T: BigObject> BigTimeSeries<T> {
+    pub fn new(window: u64) -> Self {
+        assert!(window > 0);
+        Self {
+            window,
+            len: 0,
+            next_seq: 0,
+            windows: Ordered::default(),
+        }
+    }
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn append(&mut self, timestamp: u64, value: T) {
+        let index = timestamp / self.window;
+        if self.windows.get(&index).is_none() {
+            self.windows.insert(index, Window::default());
+        }
+        let window = self.windows.get_mut(&index).unwrap();
+        window.points.insert((timestamp, self.next_seq), value);
+        window.len += 1;
+        self.next_seq += 1;
+        self.len += 1;
+    }
+    pub fn range<R: RangeBounds<u64>>(&self, range: R) -> impl Iterator<Item = (u64, &T)> + '_ {
+        let window_start = match range.start_bound() {
+            Bound::Included(start) | Bound::Excluded(start) => Bound::Included(start / self.window),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let window_end = match range.end_bound() {
+            Bound::Included(end) | Bound::Excluded(end) => Bound::Included(end / self.window),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let start = match range.start_bound() {
+            Bound::Included(start) => Bound::Included((*start, 0)),
+            Bound::Excluded(start) => Bound::Excluded((*start, u64::MAX)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => Bound::Included((*end, u64::MAX)),
+            Bound::Excluded(end) => Bound::Excluded((*end, 0)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        self.windows
+            .range((window_start, window_end))
+            .flat_map(move |(_, window)| window.points.range((start, end)))
+            .map(|((timestamp, _), value)| (timestamp, value))
+    }
+    pub fn downsample<R: RangeBounds<u64>>(
+        &self,
+        range: R,
+        bucket: u64,
+    ) -> impl Iterator<Item = (u64, Vec<&T>)> + '_ {
+        assert!(bucket > 0);
+        let mut points = self.range(range).peekable();
+        std::iter::from_fn(move || {
+            let (timestamp, value) = points.next()?;
+            let start = timestamp - timestamp % bucket;
+            let mut values = vec![value];
+            while let Some((_, value)) =
+                points.next_if(|(timestamp, _)| *timestamp < start + bucket)
+            {
+                values.push(value);
+            }
+            Some((start, values))
+        })
+    }
+    pub fn drop_before(&mut self, cutoff: u64) -> u64 {
+        let cutoff_window = cutoff / self.window;
+        let expired: Vec<(u64, u64)> = self
+            .windows
+            .range(..cutoff_window)
+            .map(|(index, window)| (index, window.len))
+            .collect();
+        let mut dropped = 0;
+        for (index, len) in expired {
+            self.windows.remove(&index);
+            dropped += len;
+        }
+        let expired: Vec<(u64, u64)> = self
+            .windows
+            .get(&cutoff_window)
+            .map(|window| {
+                window
+                    .points
+                    .range(..(cutoff, 0))
+                    .map(|(key, _)| key)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !expired.is_empty() {
+            let window = self.windows.get_mut(&cutoff_window).unwrap();
+            for key in &expired {
+                window.points.remove(key);
+            }
+            window.len -= expired.len() as u64;
+            if window.len == 0 {
+                self.windows.remove(&cutoff_window);
+            }
+            dropped += expired.len() as u64;
+        }
+        self.len -= dropped;
+        dropped
+    }
+    pub fn clear(&mut self) {
+        self.windows.clear();
+        self.len = 0;
+    }
+}
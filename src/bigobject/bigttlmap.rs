@@ -0,0 +1,139 @@
+use std::{
+    borrow::Borrow,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bigobject_derive::BigObject;
+use serde::{Deserialize, Serialize};
+
+use crate as bigobject;
+use crate::{
+    bigobject::{
+        bigmap::{Key, KeyRef},
+        BigObject,
+    },
+    BigMap,
+};
+
+#[derive(BigObject, Serialize, Deserialize)]
+#[serde(bound = "")]
+struct TtlEntry<V: BigObject> {
+    expires_at: u64,
+    value: V,
+}
+
+#[derive(BigObject, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BigTtlMap<K: Key, V: BigObject> {
+    entries: BigMap<K, TtlEntry<V>>,
+    expiry: BigMap<(u64, K), ()>,
+}
+
+impl<K: Key, V: BigObject> Default for BigTtlMap<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: BigMap::default(),
+            expiry: BigMap::default(),
+        }
+    }
+}
+
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_millis()
+        .try_into()
+        .unwrap()
+}
+
+impl<K: Key, V: BigObject> BigTtlMap<K, V> {
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized,
+    {
+        let now = millis(SystemTime::now());
+        self.entries
+            .get(key)
+            .filter(|entry| entry.expires_at > now)
+            .map(|entry| &entry.value)
+    }
+    pub async fn get_async<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized,
+    {
+        let now = millis(SystemTime::now());
+        self.entries
+            .get_async(key)
+            .await
+            .filter(|entry| entry.expires_at > now)
+            .map(|entry| &entry.value)
+    }
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized + ToOwned<Owned = K>,
+    {
+        let now = millis(SystemTime::now());
+        self.entries
+            .get_mut(key)
+            .filter(|entry| entry.expires_at > now)
+            .map(|entry| &mut entry.value)
+    }
+    pub fn expires_at<Q>(&self, key: &Q) -> Option<SystemTime>
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized,
+    {
+        self.entries
+            .get(key)
+            .map(|entry| UNIX_EPOCH + Duration::from_millis(entry.expires_at))
+    }
+    pub fn insert(&mut self, key: K, value: V, ttl: Duration) {
+        self.insert_until(key, value, SystemTime::now() + ttl);
+    }
+    pub fn insert_until(&mut self, key: K, value: V, expires_at: SystemTime) {
+        self.remove(&key);
+        let expires_at = millis(expires_at);
+        self.expiry.insert((expires_at, key.clone()), ());
+        self.entries.insert(key, TtlEntry { expires_at, value });
+    }
+    pub fn remove<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: KeyRef + ?Sized + ToOwned<Owned = K>,
+    {
+        if let Some(expires_at) = self.entries.get(key).map(|entry| entry.expires_at) {
+            self.expiry.remove(&(expires_at, key.to_owned()));
+            self.entries.remove(key);
+        }
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        let now = millis(SystemTime::now());
+        self.entries
+            .iter()
+            .filter(move |(_, entry)| entry.expires_at > now)
+            .map(|(key, entry)| (key, &entry.value))
+    }
+    pub fn remove_expired(&mut self) -> usize {
+        let now = millis(SystemTime::now());
+        let sorted = self.expiry.is_sorted();
+        let expired: Vec<(u64, K)> = self
+            .expiry
+            .iter()
+            .map(|(key, _)| key)
+            .take_while(|(expires_at, _)| !sorted || *expires_at <= now)
+            .filter(|(expires_at, _)| *expires_at <= now)
+            .collect();
+        for (expires_at, key) in &expired {
+            self.expiry.remove(&(*expires_at, key.clone()));
+            self.entries.remove(key);
+        }
+        expired.len()
+    }
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.expiry.clear();
+    }
+}
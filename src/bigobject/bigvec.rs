@@ -1,7 +1,14 @@
-use std::ops::{Index, IndexMut};
+use std::{
+    fmt,
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+};
 
 use bigobject_derive::BigObject;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{
+    de::{SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
 use crate as bigobject;
 use crate::{bigobject::BigObject, BigMap};
@@ -14,16 +21,44 @@ pub struct BigVec<T: BigObject> {
 
 impl<V: BigObject> Serialize for BigVec<V> {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        self.len.serialize(serializer)
+        if serializer.is_human_readable() {
+            serializer.collect_seq(self.iter())
+        } else {
+            self.len.serialize(serializer)
+        }
     }
 }
 
 impl<'a, V: BigObject> Deserialize<'a> for BigVec<V> {
     fn deserialize<D: Deserializer<'a>>(deserializer: D) -> Result<Self, D::Error> {
-        Ok(Self {
-            len: u64::deserialize(deserializer)?,
-            data: BigMap::default(),
-        })
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_seq(ValuesVisitor(PhantomData))
+        } else {
+            Ok(Self {
+                len: u64::deserialize(deserializer)?,
+                data: BigMap::default(),
+            })
+        }
+    }
+}
+
+struct ValuesVisitor<V>(PhantomData<V>);
+
+impl<'a, V: BigObject> Visitor<'a> for ValuesVisitor<V> {
+    type Value = BigVec<V>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence")
+    }
+    fn visit_seq<A: SeqAccess<'a>>(self, mut seq: A) -> Result<BigVec<V>, A::Error> {
+        let mut vec = BigVec {
+            len: 0,
+            data: BigMap::importing(),
+        };
+        while let Some(value) = seq.next_element()? {
+            vec.push(value);
+        }
+        Ok(vec)
     }
 }
 
@@ -94,7 +129,7 @@ impl<T: BigObject> BigVec<T> {
     pub fn is_empty(&self) -> bool {
         self.len == 0
     }
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             data: &self.data,
             index: 0,
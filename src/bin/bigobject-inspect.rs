@@ -0,0 +1,133 @@
+use std::{fmt::Write, path::PathBuf};
+
+use bigobject::{
+    internal::{db_opts, Codec, Prefix},
+    Compression, DbOptions,
+};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, byte| {
+        write!(out, "{byte:02x}").unwrap();
+        out
+    })
+}
+
+fn parse_hex(hex: &str) -> Vec<u8> {
+    assert!(hex.len().is_multiple_of(2), "Odd number of hex digits");
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("Invalid hex digits"))
+        .collect()
+}
+
+fn field_path(prefix: &[u8]) -> String {
+    prefix
+        .iter()
+        .map(|byte| byte.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn map_key(codec: &Codec, stored: &[u8]) -> String {
+    let Some(bytes) = codec.unseal_map_key(stored) else {
+        return format!("<sealed> {}", hex(stored));
+    };
+    match bytes.split_last() {
+        Some((0, text)) if !text.contains(&0) => match std::str::from_utf8(text) {
+            Ok(text) => format!("{} {:?}", hex(&bytes), text),
+            Err(_) => hex(&bytes),
+        },
+        _ => hex(&bytes),
+    }
+}
+
+fn options() -> DbOptions {
+    let mut options = DbOptions::default();
+    let dictionary = std::env::var_os("BIGOBJECT_DICTIONARY");
+    if std::env::var_os("BIGOBJECT_COMPRESSION").is_some() || dictionary.is_some() {
+        options.compression = Some(Compression {
+            dictionary: dictionary
+                .map(|path| std::fs::read(path).expect("Failed to read dictionary")),
+            ..Default::default()
+        });
+    }
+    #[cfg(feature = "encryption")]
+    if let Ok(key) = std::env::var("BIGOBJECT_KEY") {
+        let parse_key = |key: &str| -> [u8; 32] {
+            parse_hex(key)
+                .try_into()
+                .expect("Encryption keys must be 32 bytes")
+        };
+        options.encryption = Some(bigobject::Encryption {
+            key: parse_key(&key),
+            previous_keys: Vec::new(),
+            map_key: std::env::var("BIGOBJECT_MAP_KEY")
+                .ok()
+                .map(|map_key| parse_key(&map_key)),
+            previous_map_key: None,
+        });
+    }
+    options
+}
+
+fn value(codec: &Codec, stored: &[u8]) -> String {
+    let Some((encoded, _)) = codec.unseal(stored) else {
+        return format!("<sealed> {}", hex(stored));
+    };
+    match rmp_serde::from_slice::<serde_json::Value>(&encoded) {
+        Ok(value) => serde_json::to_string_pretty(&value).unwrap(),
+        Err(err) => format!("<undecodable: {err}> {}", hex(stored)),
+    }
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let (Some(path), filter) = (args.next().map(PathBuf::from), args.next()) else {
+        eprintln!("Usage: bigobject-inspect <db-path> [hex-key-prefix]");
+        eprintln!("Environment: BIGOBJECT_COMPRESSION=1, BIGOBJECT_DICTIONARY=<path>,");
+        eprintln!("             BIGOBJECT_KEY=<hex>, BIGOBJECT_MAP_KEY=<hex>");
+        std::process::exit(2);
+    };
+    let filter = filter.as_deref().map(parse_hex).unwrap_or_default();
+    let options = options();
+    let codec = Codec::new(&options);
+    let mut db_options = db_opts(&options);
+    let summing = std::sync::Arc::new(Codec::new(&options));
+    // Without the keys sealed counter operands cannot be summed, so the newest one is shown.
+    db_options.set_merge_operator_associative(
+        "BigObjectCounterAdd",
+        move |_key, existing, operands| {
+            summing
+                .sum_counters(existing.into_iter().chain(operands))
+                .or_else(|| operands.into_iter().last().or(existing).map(<[u8]>::to_vec))
+        },
+    );
+    let rocksdb =
+        rocksdb::DB::open_for_read_only(&db_options, path, false).expect("Failed to open database");
+    for kv in rocksdb.iterator(rocksdb::IteratorMode::From(
+        &filter,
+        rocksdb::Direction::Forward,
+    )) {
+        let (key, encoded) = kv.unwrap();
+        if !key.starts_with(&filter) {
+            break;
+        }
+        if key.as_ref() == [0] {
+            println!("root = {}", value(&codec, &encoded));
+            continue;
+        }
+        match Prefix::split_leaf(&key) {
+            Some((prefix, key_bytes)) => println!(
+                "[{}] {} = {}",
+                field_path(prefix),
+                map_key(&codec, key_bytes),
+                value(&codec, &encoded)
+            ),
+            None => println!(
+                "<malformed key> {} = {}",
+                hex(&key),
+                value(&codec, &encoded)
+            ),
+        }
+    }
+}
@@ -1,12 +1,36 @@
 mod bigobject;
 mod storage;
 
+#[cfg(feature = "encryption")]
+pub use crate::storage::encryption::Encryption;
 pub use crate::{
-    bigobject::{bigmap::BigMap, bigvec::BigVec},
-    storage::db::Db,
+    bigobject::{
+        bigbitmap::BigBitmap,
+        bigbytes::BigBytes,
+        bigcountermap::BigCounterMap,
+        bigheap::BigHeap,
+        bigindexedmap::{AnyMapIndex, BigIndexedMap, MapIndex, MapIndexes},
+        biglog::BigLog,
+        bigmap::BigMap,
+        bigmultimap::BigMultiMap,
+        bigtimeseries::BigTimeSeries,
+        bigttlmap::BigTtlMap,
+        bigvec::BigVec,
+        BigObject,
+    },
+    storage::{
+        compression::Compression,
+        db::{Db, ReadOnlyDb},
+        options::{DbOptions, Durability},
+        stats::DbStats,
+        verify::VerifyReport,
+    },
 };
 pub use bigobject_derive::BigObject;
 
 pub mod internal {
-    pub use crate::{bigobject::BigObject, storage::batch::Batch, storage::prefix::Prefix};
+    pub use crate::{
+        bigobject::BigObject, storage::batch::Batch, storage::codec::Codec, storage::db::db_opts,
+        storage::prefix::Prefix, storage::verify::Verifier,
+    };
 }
@@ -1,5 +1,16 @@
 pub mod batch;
+pub mod bulk;
+pub mod codec;
+pub mod compression;
 pub mod db;
-pub mod prefix;
+#[cfg(feature = "encryption")]
+pub mod encryption;
 pub mod guard;
+pub mod json;
 pub mod lock_context;
+pub mod options;
+pub mod prefix;
+pub mod staging;
+pub mod stats;
+pub mod trace;
+pub mod verify;
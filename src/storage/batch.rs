@@ -1,56 +1,223 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc, time::Instant};
 
 use crate::{
     bigobject::{bigmap::KeyRef, BigObject},
     storage::{
-        db::{CacheEntry, DbInner, SyncWrapper},
+        bulk::{self, BulkRun, SstEntry},
+        db::{CacheEntry, DbInner},
+        lock_context::LockContext,
+        options::Durability,
         prefix::Prefix,
+        staging::Staging,
+        trace,
     },
 };
 
+enum Op {
+    Put(Vec<u8>, Vec<u8>),
+    Delete(Vec<u8>),
+    Merge(Vec<u8>, Vec<u8>),
+    DeleteRange(Vec<u8>, Vec<u8>),
+}
+
 #[derive(Default)]
 pub struct Batch {
-    pub(crate) rocksdb: rocksdb::WriteBatch,
+    ops: Vec<Op>,
     cache_inserts: Vec<(Vec<u8>, CacheEntry)>,
     cache_entry_deletes: Vec<Vec<u8>>,
     cache_prefix_deletes: Vec<Vec<u8>>,
+    cache_invalidations: Vec<Vec<u8>>,
+    ingest_files: Vec<PathBuf>,
+    staging: Option<Arc<Staging>>,
+    durability: Option<Durability>,
 }
 
 impl Batch {
+    pub(crate) fn spilling(staging: Arc<Staging>) -> Self {
+        Self {
+            staging: Some(staging),
+            ..Default::default()
+        }
+    }
+    pub(crate) fn is_spilling(&self) -> bool {
+        self.staging.is_some()
+    }
+    pub(crate) fn set_durability(&mut self, durability: Durability) {
+        self.durability = Some(durability);
+    }
     pub(crate) fn put<T: BigObject, K: KeyRef>(&mut self, prefix: &Prefix, key: &K, mut value: T) {
+        if T::INDEXED {
+            self.update_index::<T, K>(prefix, key, value.index_leaves(prefix, key));
+        }
         let mut prefix = prefix.clone();
         let prefix_len = prefix.append_map_key(key);
         value.finalize(|| &mut prefix, self);
-        let encoded = rmp_serde::to_vec(&value).unwrap();
+        let encoded = prefix
+            .db()
+            .codec
+            .encode_value(rmp_serde::to_vec(&value).unwrap());
         let db_key = prefix.into_leaf(prefix_len);
+        if let Some(staging) = &self.staging {
+            staging.put(&db_key, &encoded);
+            return;
+        }
         let len = (db_key.len() + encoded.len()) as u32;
-        self.rocksdb.put(&db_key, encoded);
+        self.ops.push(Op::Put(db_key.clone(), encoded));
         self.cache_inserts.push((
             db_key,
             CacheEntry {
                 len,
-                value: Some(Arc::new(SyncWrapper(value))),
+                value: Some(Arc::new(value)),
             },
         ));
     }
-    pub(crate) fn delete<K: KeyRef>(&mut self, prefix: &Prefix, key: &K) {
+    pub(crate) fn merge<K: KeyRef>(&mut self, prefix: &Prefix, key: &K, delta: i64) {
+        let db = prefix.db();
+        let mut prefix = prefix.clone();
+        let prefix_len = prefix.append_map_key(key);
+        let db_key = prefix.into_leaf(prefix_len);
+        let encoded = db.codec.encode_counter(delta);
+        if let Some(staging) = &self.staging {
+            staging.merge(&db, &db_key, &encoded);
+            return;
+        }
+        self.ops.push(Op::Merge(db_key.clone(), encoded));
+        self.cache_invalidations.push(db_key);
+    }
+    pub(crate) fn put_raw(&mut self, db_key: &[u8], encoded: &[u8]) {
+        match &self.staging {
+            Some(staging) => staging.put(db_key, encoded),
+            None => self.ops.push(Op::Put(db_key.to_vec(), encoded.to_vec())),
+        }
+    }
+    pub(crate) fn delete<T: BigObject, K: KeyRef>(&mut self, prefix: &Prefix, key: &K) {
+        if T::INDEXED {
+            self.update_index::<T, K>(prefix, key, Vec::new());
+        }
         let mut prefix = prefix.clone();
         let prefix_len = prefix.append_map_key(key);
         self.delete_prefix(&prefix);
         let db_key = prefix.into_leaf(prefix_len);
-        self.rocksdb.delete(&db_key);
+        if let Some(staging) = &self.staging {
+            staging.delete(&db_key);
+            return;
+        }
+        self.ops.push(Op::Delete(db_key.clone()));
         self.cache_entry_deletes.push(db_key);
     }
+    fn update_index<T: BigObject, K: KeyRef>(
+        &mut self,
+        prefix: &Prefix,
+        key: &K,
+        leaves: Vec<Vec<u8>>,
+    ) {
+        let mut stored = prefix.without_relocation();
+        if let Some(relocation) = &prefix.relocation {
+            let (from, to) = &**relocation;
+            if let Some(rest) = prefix.key.strip_prefix(&to[..]) {
+                stored.key = [from, rest].concat();
+            }
+        }
+        let old_leaves = LockContext::get::<T, K>(&stored, key)
+            .map(|old| old.index_leaves(prefix, key))
+            .unwrap_or_default();
+        for leaf in &old_leaves {
+            if !leaves.contains(leaf) {
+                self.delete_raw(leaf);
+            }
+        }
+        let encoded = prefix
+            .db()
+            .codec
+            .encode_value(rmp_serde::to_vec(&()).unwrap());
+        for leaf in leaves {
+            self.put_raw(&leaf, &encoded);
+        }
+    }
+    pub(crate) fn delete_raw(&mut self, db_key: &[u8]) {
+        if let Some(staging) = &self.staging {
+            staging.delete(db_key);
+            return;
+        }
+        self.ops.push(Op::Delete(db_key.to_vec()));
+        self.cache_entry_deletes.push(db_key.to_vec());
+    }
     pub(crate) fn delete_prefix(&mut self, prefix: &Prefix) {
-        let next_prefix = prefix.next_prefix();
-        let next_prefix_len = next_prefix.len();
-        let from = prefix.clone().into_leaf(prefix.len());
-        let to = next_prefix.into_leaf(next_prefix_len);
-        self.rocksdb.delete_range(&from, &to);
-        self.cache_prefix_deletes.push(from);
+        let from = prefix.key.clone();
+        let to = prefix.next_prefix().key;
+        if let Some(staging) = &self.staging {
+            staging.delete_range(&from, &to);
+            return;
+        }
+        self.cache_prefix_deletes.push(from.clone());
+        self.ops.push(Op::DeleteRange(from, to));
+    }
+    pub(crate) fn copy_prefix(&mut self, from: &Prefix, to: &Prefix) {
+        let range = (from.key.clone(), Some(from.next_prefix().key));
+        for (db_key, encoded) in LockContext::raw_iter(from, range) {
+            if let Some(db_key) = to.relocate_leaf(&db_key, from.len()) {
+                self.put_raw(&db_key, &encoded);
+            }
+        }
+    }
+    pub(crate) fn ingest(&mut self, prefix: &Prefix, run: BulkRun) {
+        let entries = run
+            .entries()
+            .map(|(map_key, encoded)| (prefix.stored_leaf(&map_key), encoded));
+        if let Some(staging) = &self.staging {
+            for (db_key, encoded) in entries {
+                staging.put(&db_key, &encoded);
+            }
+            return;
+        }
+        let entries = entries.map(|(db_key, encoded)| (db_key, SstEntry::Put(encoded.into_vec())));
+        self.ingest_files
+            .extend(bulk::write_sst_files(&prefix.db(), entries));
+        self.cache_prefix_deletes.push(prefix.key.clone());
     }
     pub(super) fn apply(self, db: &DbInner) {
-        db.rocksdb.write(self.rocksdb).unwrap();
+        trace::span!("bigobject::apply", ops = self.ops.len());
+        let start = Instant::now();
+        if let Some(staging) = &self.staging {
+            let (ops, bytes) = staging.commit(db, Vec::new());
+            db.stats.record_commit(ops, bytes, start.elapsed());
+            return;
+        }
+        let durability = self.durability.unwrap_or(db.options.durability);
+        let (ops, bytes) = self.write(db, durability);
+        db.stats.record_commit(ops, bytes, start.elapsed());
+    }
+    fn write(self, db: &DbInner, durability: Durability) -> (usize, usize) {
+        let counts = if self.ingest_files.is_empty() {
+            let mut batch = rocksdb::WriteBatch::default();
+            for op in self.ops {
+                match op {
+                    Op::Put(db_key, encoded) => batch.put(db_key, encoded),
+                    Op::Delete(db_key) => batch.delete(db_key),
+                    Op::Merge(db_key, encoded) => batch.merge(db_key, encoded),
+                    Op::DeleteRange(from, to) => batch.delete_range(from, to),
+                }
+            }
+            let counts = (batch.len(), batch.size_in_bytes());
+            db.rocksdb
+                .write_opt(batch, &durability.write_opts())
+                .unwrap();
+            if let Some(wal_sync) = &db.wal_sync {
+                wal_sync.after_write(&db.rocksdb, durability);
+            }
+            counts
+        } else {
+            let staging = Staging::create(db);
+            for op in self.ops {
+                match op {
+                    Op::Put(db_key, encoded) => staging.put(&db_key, &encoded),
+                    Op::Delete(db_key) => staging.delete(&db_key),
+                    Op::Merge(db_key, encoded) => staging.merge(db, &db_key, &encoded),
+                    Op::DeleteRange(from, to) => staging.delete_range(&from, &to),
+                }
+            }
+            staging.commit(db, self.ingest_files)
+        };
         if !self.cache_prefix_deletes.is_empty() {
             db.cache
                 .invalidate_entries_if(move |key, _value| {
@@ -60,6 +227,9 @@ impl Batch {
                 })
                 .unwrap();
         }
+        for key in self.cache_invalidations {
+            db.cache.invalidate(&key);
+        }
         for key in self.cache_entry_deletes {
             let len = key.len();
             db.cache.insert(
@@ -73,5 +243,6 @@ impl Batch {
         for (key, value) in self.cache_inserts {
             db.cache.insert(key, value);
         }
+        counts
     }
 }
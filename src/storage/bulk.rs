@@ -0,0 +1,204 @@
+use std::{
+    ffi::CStr,
+    io::ErrorKind,
+    mem::{size_of, take, ManuallyDrop},
+    path::{Path, PathBuf},
+    ptr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use librocksdb_sys as ffi;
+
+use crate::storage::{
+    db::{db_opts, DbInner},
+    staging::{Kv, SPILL_CHANGES},
+    trace,
+};
+
+const MAX_FILE_SIZE: u64 = 256 * 1024 * 1024;
+
+const TMP_DIR: &str = "bigobject-tmp";
+
+static NEXT_FILE: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) enum SstEntry {
+    Put(Vec<u8>),
+    Delete,
+    Merge(Vec<u8>),
+}
+
+pub(crate) fn unique_path(db: &DbInner, name: &str) -> PathBuf {
+    let dir = db.rocksdb.path().join(TMP_DIR);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(format!(
+        "{name}-{}-{}",
+        std::process::id(),
+        NEXT_FILE.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+pub(crate) fn remove_leftovers(path: &Path) {
+    match std::fs::remove_dir_all(path.join(TMP_DIR)) {
+        Err(err) if err.kind() != ErrorKind::NotFound => panic!("{err}"),
+        _ => {}
+    }
+}
+
+pub(crate) fn write_sst_files(
+    db: &DbInner,
+    entries: impl Iterator<Item = (Vec<u8>, SstEntry)>,
+) -> Vec<PathBuf> {
+    write_files(db, &db_opts(&db.options), entries)
+}
+
+fn write_files(
+    db: &DbInner,
+    opts: &rocksdb::Options,
+    entries: impl Iterator<Item = (Vec<u8>, SstEntry)>,
+) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut entries = entries.peekable();
+    while entries.peek().is_some() {
+        let path = unique_path(db, "bulk").with_extension("sst");
+        let mut writer = rocksdb::SstFileWriter::create(opts);
+        writer.open(&path).unwrap();
+        for (db_key, entry) in entries.by_ref() {
+            match entry {
+                SstEntry::Put(encoded) => writer.put(db_key, encoded),
+                SstEntry::Delete => writer.delete(db_key),
+                SstEntry::Merge(encoded) => writer.merge(db_key, encoded),
+            }
+            .unwrap();
+            if writer.file_size() >= MAX_FILE_SIZE {
+                break;
+            }
+        }
+        writer.finish().unwrap();
+        files.push(path);
+    }
+    files
+}
+
+fn delete_range(writer: &mut rocksdb::SstFileWriter, from: &[u8], to: &[u8]) {
+    // SstFileWriter only wraps the C handle and has no delete_range of its own.
+    assert_eq!(
+        size_of::<rocksdb::SstFileWriter>(),
+        size_of::<*mut ffi::rocksdb_sstfilewriter_t>()
+    );
+    let mut err = ptr::null_mut();
+    unsafe {
+        let inner =
+            *(writer as *mut rocksdb::SstFileWriter as *const *mut ffi::rocksdb_sstfilewriter_t);
+        ffi::rocksdb_sstfilewriter_delete_range(
+            inner,
+            from.as_ptr() as *const _,
+            from.len(),
+            to.as_ptr() as *const _,
+            to.len(),
+            &mut err,
+        );
+    }
+    assert!(
+        err.is_null(),
+        "{}",
+        unsafe { CStr::from_ptr(err) }.to_string_lossy()
+    );
+}
+
+pub(crate) fn write_range_deletions(
+    db: &DbInner,
+    ranges: impl Iterator<Item = (Vec<u8>, Vec<u8>)>,
+) -> Vec<PathBuf> {
+    let mut ranges = ranges.peekable();
+    if ranges.peek().is_none() {
+        return Vec::new();
+    }
+    let opts = db_opts(&db.options);
+    let path = unique_path(db, "bulk").with_extension("sst");
+    let mut writer = rocksdb::SstFileWriter::create(&opts);
+    writer.open(&path).unwrap();
+    for (from, to) in ranges {
+        delete_range(&mut writer, &from, &to);
+    }
+    writer.finish().unwrap();
+    vec![path]
+}
+
+fn ingest_files(rocksdb: &rocksdb::DB, files: Vec<PathBuf>) {
+    if files.is_empty() {
+        return;
+    }
+    let mut opts = rocksdb::IngestExternalFileOptions::default();
+    opts.set_move_files(true);
+    rocksdb.ingest_external_file_opts(&opts, files).unwrap();
+}
+
+pub(crate) fn ingest(db: &DbInner, files: Vec<PathBuf>) {
+    ingest_files(&db.rocksdb, files);
+}
+
+/// Entries of a pending `bulk_load`, keyed by their stored map key so that they can be written
+/// under whichever prefix the map has at commit.
+pub(crate) struct BulkRun {
+    rocksdb: ManuallyDrop<rocksdb::DB>,
+    path: PathBuf,
+}
+
+impl BulkRun {
+    pub(crate) fn create(db: &DbInner) -> Self {
+        let path = unique_path(db, "run");
+        let mut opts = rocksdb::Options::default();
+        opts.create_if_missing(true);
+        opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
+        Self {
+            rocksdb: ManuallyDrop::new(rocksdb::DB::open(&opts, &path).unwrap()),
+            path,
+        }
+    }
+
+    fn write(&self, batch: rocksdb::WriteBatch) {
+        let mut opts = rocksdb::WriteOptions::default();
+        opts.disable_wal(true);
+        self.rocksdb.write_opt(batch, &opts).unwrap();
+    }
+
+    pub(crate) fn load(&self, db: &DbInner, entries: impl Iterator<Item = (Vec<u8>, Vec<u8>)>) {
+        if db.codec.sorted_map_keys() {
+            let entries = entries.map(|(map_key, encoded)| (map_key, SstEntry::Put(encoded)));
+            let files = write_files(db, &rocksdb::Options::default(), entries);
+            ingest_files(&self.rocksdb, files);
+            return;
+        }
+        let mut batch = rocksdb::WriteBatch::default();
+        for (map_key, encoded) in entries {
+            batch.put(map_key, encoded);
+            if batch.len() >= SPILL_CHANGES {
+                self.write(take(&mut batch));
+            }
+        }
+        self.write(batch);
+    }
+
+    pub(crate) fn put_if_absent(&self, map_key: &[u8], encoded: &[u8]) {
+        if self.rocksdb.get_pinned(map_key).unwrap().is_none() {
+            let mut batch = rocksdb::WriteBatch::default();
+            batch.put(map_key, encoded);
+            self.write(batch);
+        }
+    }
+
+    pub(crate) fn entries(&self) -> impl Iterator<Item = Kv> + '_ {
+        self.rocksdb
+            .iterator(rocksdb::IteratorMode::Start)
+            .map(Result::unwrap)
+    }
+}
+
+impl Drop for BulkRun {
+    fn drop(&mut self) {
+        unsafe { ManuallyDrop::drop(&mut self.rocksdb) };
+        if let Err(err) = rocksdb::DB::destroy(&rocksdb::Options::default(), &self.path) {
+            trace::warning!("Failed to remove bulk run {}: {err}", self.path.display());
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::borrow::Cow;
+
+use crate::{
+    bigobject::bigmap::{Key, KeyRef},
+    storage::{
+        compression::{decompress, Compressor},
+        options::DbOptions,
+    },
+};
+
+#[cfg(feature = "encryption")]
+use crate::storage::encryption::{encrypt_map_key, Ciphers};
+
+const ENVELOPE: u8 = 0xC1;
+const COMPRESSED: u8 = 2;
+#[cfg(feature = "encryption")]
+const ENCRYPTED: u8 = 1;
+
+pub struct Codec {
+    compressor: Option<Compressor>,
+    #[cfg(feature = "encryption")]
+    ciphers: Option<Ciphers>,
+}
+
+impl Codec {
+    pub fn new(options: &DbOptions) -> Self {
+        Self {
+            compressor: options.compression.as_ref().map(Compressor::new),
+            #[cfg(feature = "encryption")]
+            ciphers: options.encryption.as_ref().map(Ciphers::new),
+        }
+    }
+
+    pub(crate) fn encode_value(&self, encoded: Vec<u8>) -> Vec<u8> {
+        let frame = self
+            .compressor
+            .as_ref()
+            .and_then(|compressor| compressor.compress(&encoded));
+        let encoded = match frame {
+            Some(frame) => [&[ENVELOPE, COMPRESSED][..], &frame].concat(),
+            None => encoded,
+        };
+        #[cfg(feature = "encryption")]
+        if let Some(ciphers) = &self.ciphers {
+            let mut sealed = vec![ENVELOPE, ENCRYPTED];
+            ciphers.encrypt(&encoded, &mut sealed);
+            return sealed;
+        }
+        encoded
+    }
+
+    pub fn unseal<'a>(&self, stored: &'a [u8]) -> Option<(Cow<'a, [u8]>, bool)> {
+        match stored {
+            #[cfg(feature = "encryption")]
+            [ENVELOPE, ENCRYPTED, sealed @ ..] => {
+                let (mut plain, current) = self.ciphers.as_ref()?.decrypt(sealed)?;
+                if plain.first() == Some(&ENVELOPE) {
+                    plain = self.unseal(&plain)?.0.into_owned();
+                }
+                Some((Cow::Owned(plain), !current))
+            }
+            [ENVELOPE, COMPRESSED, frame @ ..] => Some((
+                Cow::Owned(decompress(self.compressor.as_ref(), frame)?),
+                false,
+            )),
+            [ENVELOPE, ..] => None,
+            _ => Some((Cow::Borrowed(stored), false)),
+        }
+    }
+
+    pub(crate) fn decode_value<'a>(&self, stored: &'a [u8]) -> Cow<'a, [u8]> {
+        self.unseal(stored)
+            .expect("Failed to decode stored value")
+            .0
+    }
+
+    pub(crate) fn encode_counter(&self, value: i64) -> Vec<u8> {
+        self.encode_value(rmp_serde::to_vec(&value).unwrap())
+    }
+
+    pub fn sum_counters<'a>(&self, stored: impl IntoIterator<Item = &'a [u8]>) -> Option<Vec<u8>> {
+        let mut sum = 0i64;
+        for stored in stored {
+            let (encoded, _) = self.unseal(stored)?;
+            sum = sum.wrapping_add(rmp_serde::from_slice(&encoded).ok()?);
+        }
+        Some(self.encode_counter(sum))
+    }
+
+    pub(crate) fn sorted_map_keys(&self) -> bool {
+        #[cfg(feature = "encryption")]
+        if let Some(ciphers) = &self.ciphers {
+            return ciphers.map_key().is_none();
+        }
+        true
+    }
+
+    pub(crate) fn encode_map_key<K: KeyRef>(&self, map_key: &K, out: &mut Vec<u8>) {
+        #[cfg(feature = "encryption")]
+        if let Some(cipher) = self.ciphers.as_ref().and_then(|ciphers| ciphers.map_key()) {
+            let plain = storekey::serialize(map_key).unwrap();
+            out.extend_from_slice(&encrypt_map_key(cipher, &plain));
+            return;
+        }
+        storekey::serialize_into(out, map_key).unwrap();
+    }
+
+    pub(crate) fn reseal_map_key(&self, stored: &[u8]) -> Option<Vec<u8>> {
+        #[cfg(feature = "encryption")]
+        if let Some(cipher) = self.ciphers.as_ref().and_then(|ciphers| ciphers.map_key()) {
+            return Some(encrypt_map_key(cipher, &self.unseal_map_key(stored)?));
+        }
+        Some(stored.to_vec())
+    }
+
+    pub fn unseal_map_key<'a>(&self, stored: &'a [u8]) -> Option<Cow<'a, [u8]>> {
+        #[cfg(feature = "encryption")]
+        if let Some(ciphers) = &self.ciphers {
+            if ciphers.map_key().is_some() {
+                return ciphers.decrypt_map_key(stored).map(Cow::Owned);
+            }
+        }
+        Some(Cow::Borrowed(stored))
+    }
+
+    pub(crate) fn decode_map_key<K: Key>(&self, stored: &[u8]) -> Option<K> {
+        storekey::deserialize(&self.unseal_map_key(stored)?).ok()
+    }
+}
@@ -0,0 +1,72 @@
+use std::io::Read;
+
+use zstd::dict::{DecoderDictionary, EncoderDictionary};
+
+#[derive(Debug, Clone)]
+pub struct Compression {
+    pub min_size: usize,
+    pub level: i32,
+    pub dictionary: Option<Vec<u8>>,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            min_size: 64 * 1024,
+            level: 3,
+            dictionary: None,
+        }
+    }
+}
+
+pub(crate) struct Compressor {
+    min_size: usize,
+    level: i32,
+    dictionary: Option<(EncoderDictionary<'static>, DecoderDictionary<'static>)>,
+}
+
+impl Compressor {
+    pub(crate) fn new(compression: &Compression) -> Self {
+        Self {
+            min_size: compression.min_size,
+            level: compression.level,
+            dictionary: compression.dictionary.as_ref().map(|dictionary| {
+                (
+                    EncoderDictionary::copy(dictionary, compression.level),
+                    DecoderDictionary::copy(dictionary),
+                )
+            }),
+        }
+    }
+
+    pub(crate) fn compress(&self, encoded: &[u8]) -> Option<Vec<u8>> {
+        if encoded.len() < self.min_size {
+            return None;
+        }
+        let mut compressor = match &self.dictionary {
+            Some((dictionary, _)) => zstd::bulk::Compressor::with_prepared_dictionary(dictionary),
+            None => zstd::bulk::Compressor::new(self.level),
+        }
+        .unwrap();
+        let compressed = compressor.compress(encoded).unwrap();
+        (compressed.len() < encoded.len()).then_some(compressed)
+    }
+}
+
+pub(crate) fn decompress(compressor: Option<&Compressor>, frame: &[u8]) -> Option<Vec<u8>> {
+    let dictionary = compressor.and_then(|compressor| compressor.dictionary.as_ref());
+    let mut decoded = Vec::new();
+    match dictionary {
+        Some((_, dictionary)) => {
+            zstd::stream::read::Decoder::with_prepared_dictionary(frame, dictionary)
+                .ok()?
+                .read_to_end(&mut decoded)
+                .ok()?
+        }
+        None => zstd::stream::read::Decoder::new(frame)
+            .ok()?
+            .read_to_end(&mut decoded)
+            .ok()?,
+    };
+    Some(decoded)
+}
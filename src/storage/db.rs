@@ -1,39 +1,105 @@
-use std::{any::Any, cell::RefCell, marker::PhantomData, path::Path, sync::Arc};
+use std::{
+    any::Any,
+    io::{Read, Write},
+    path::Path,
+    sync::{Arc, Weak},
+    time::Duration,
+};
 
-use moka::sync::Cache;
-use parking_lot::RwLock;
+use async_lock::RwLock;
+use moka::{
+    notification::RemovalCause,
+    sync::{Cache, ConcurrentCacheExt},
+};
+use rocksdb::{
+    backup::{BackupEngine, BackupEngineOptions, RestoreOptions},
+    checkpoint::Checkpoint,
+};
 
 use crate::{
     bigobject::BigObject,
     storage::{
-        guard::{RGuard, WGuard},
+        bulk,
+        codec::Codec,
+        guard::{AsyncWGuard, RGuard, WGuard},
+        json,
+        lock_context::ReadStash,
+        options::{DbOptions, Durability, WalSync},
         prefix::Prefix,
+        stats::{Counters, DbStats},
+        trace,
+        verify::{Verifier, VerifyReport},
     },
 };
 
-#[repr(transparent)]
-pub(super) struct SyncWrapper<T: BigObject>(pub(super) T);
-unsafe impl<T: BigObject> Send for SyncWrapper<T> {}
-unsafe impl<T: BigObject> Sync for SyncWrapper<T> {}
+#[cfg(feature = "encryption")]
+use crate::storage::lock_context::LockContext;
 
 #[derive(Clone)]
-pub(super) struct CacheEntry {
+pub(crate) struct CacheEntry {
     pub(super) len: u32,
     pub(super) value: Option<Arc<dyn Any + Send + Sync>>,
 }
 
-pub(super) struct DbInner {
+pub(crate) struct DbInner {
     pub rocksdb: rocksdb::DB,
     pub cache: Cache<Vec<u8>, CacheEntry>,
+    pub stats: Arc<Counters>,
+    pub read_stash: ReadStash,
+    pub options: DbOptions,
+    pub wal_sync: Option<WalSync>,
+    pub codec: Codec,
+}
+
+impl DbInner {
+    fn stats(&self) -> DbStats {
+        self.cache.sync();
+        DbStats {
+            cache_entries: self.cache.entry_count(),
+            cache_weighted_size: self.cache.weighted_size(),
+            estimate_num_keys: self
+                .rocksdb
+                .property_int_value("rocksdb.estimate-num-keys")
+                .unwrap(),
+            block_cache_usage: self
+                .rocksdb
+                .property_int_value("rocksdb.block-cache-usage")
+                .unwrap(),
+            ..self.stats.snapshot()
+        }
+    }
+}
+
+impl Drop for DbInner {
+    fn drop(&mut self) {
+        if self.wal_sync.is_some() && matches!(self.options.durability, Durability::GroupCommit(_))
+        {
+            if let Err(err) = self.rocksdb.flush_wal(true) {
+                trace::warning!("Failed to sync the WAL on close: {err}");
+            }
+        }
+    }
+}
+
+fn spawn_wal_flusher(db: Weak<DbInner>, interval: Duration) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        let Some(db) = db.upgrade() else {
+            break;
+        };
+        let wal_sync = db.wal_sync.as_ref().unwrap();
+        if let Err(err) = wal_sync.sync_if_due(&db.rocksdb, interval) {
+            trace::warning!("Failed to sync the WAL: {err}");
+        }
+    });
 }
 
 pub struct Db<T: BigObject> {
-    pub(super) inner: Arc<RwLock<DbInner>>,
-    pub(crate) root: RefCell<T>,
-    _phantom: PhantomData<T>,
+    pub(super) inner: Arc<DbInner>,
+    pub(super) root: RwLock<T>,
 }
 
-fn db_opts() -> rocksdb::Options {
+pub fn db_opts(options: &DbOptions) -> rocksdb::Options {
     let mut opts = rocksdb::Options::default();
     opts.increase_parallelism(
         std::thread::available_parallelism()
@@ -53,7 +119,6 @@ fn db_opts() -> rocksdb::Options {
     opts.set_optimize_filters_for_hits(true);
     opts.set_bytes_per_sync(1024 * 1024);
     opts.set_allow_concurrent_memtable_write(false);
-    opts.set_inplace_update_support(true);
     let mut block_opts = rocksdb::BlockBasedOptions::default();
     block_opts.set_bloom_filter(10.0, false);
     block_opts.set_cache_index_and_filter_blocks(true);
@@ -61,31 +126,212 @@ fn db_opts() -> rocksdb::Options {
     opts.set_use_adaptive_mutex(true);
     opts.set_memtable_prefix_bloom_ratio(0.1);
     opts.set_memtable_whole_key_filtering(true);
+    let codec = Arc::new(Codec::new(options));
+    opts.set_merge_operator_associative("BigObjectCounterAdd", move |_key, existing, operands| {
+        codec.sum_counters(existing.into_iter().chain(operands))
+    });
     opts.set_max_log_file_size(1024 * 1024);
     opts.set_recycle_log_file_num(5);
     opts
 }
 
+fn load_root<T: BigObject + Default>(db: &Arc<DbInner>) -> T {
+    let mut root = if let Some(encoded_root) = db.rocksdb.get([0]).unwrap() {
+        rmp_serde::from_slice(&db.codec.decode_value(&encoded_root)).unwrap()
+    } else {
+        T::default()
+    };
+    let mut prefix = Prefix::root(db);
+    root.initialize(|| &mut prefix);
+    root
+}
+
 impl<T: BigObject + Default> Db<T> {
-    pub fn open<P: AsRef<Path>>(path: P) -> Self {
-        let rocksdb = rocksdb::DB::open(&db_opts(), path).unwrap();
-        let mut root = if let Some(encoded_root) = rocksdb.get([0]).unwrap() {
-            rmp_serde::from_slice(&encoded_root).unwrap()
-        } else {
-            T::default()
-        };
-        let mut prefix = Prefix::new();
-        root.initialize(|| &mut prefix);
+    fn from_rocksdb(rocksdb: rocksdb::DB, options: DbOptions, writable: bool) -> Self {
+        let stats = Arc::new(Counters::default());
+        let eviction_stats = stats.clone();
         let cache = Cache::builder()
             .max_capacity(128 * 1024 * 1024)
             .weigher(|_key, value: &CacheEntry| value.len)
+            .eviction_listener(move |_key, _value, cause| {
+                if cause == RemovalCause::Size {
+                    eviction_stats.record_eviction();
+                }
+            })
             .support_invalidation_closures()
             .build();
+        let inner = Arc::new(DbInner {
+            rocksdb,
+            cache,
+            stats,
+            read_stash: ReadStash::default(),
+            codec: Codec::new(&options),
+            options,
+            wal_sync: writable.then(WalSync::default),
+        });
+        if let (true, Durability::GroupCommit(interval)) = (writable, inner.options.durability) {
+            spawn_wal_flusher(Arc::downgrade(&inner), interval);
+        }
         Db {
-            inner: Arc::new(RwLock::new(DbInner { rocksdb, cache })),
-            root: RefCell::new(root),
-            _phantom: PhantomData,
+            root: RwLock::new(load_root(&inner)),
+            inner,
+        }
+    }
+    pub fn open<P: AsRef<Path>>(path: P) -> Self {
+        Self::open_with_options(path, DbOptions::default())
+    }
+    pub fn open_with_options<P: AsRef<Path>>(path: P, options: DbOptions) -> Self {
+        trace::span!("bigobject::open", path = %path.as_ref().display());
+        let rocksdb = rocksdb::DB::open(&db_opts(&options), &path).unwrap();
+        bulk::remove_leftovers(path.as_ref());
+        Self::from_rocksdb(rocksdb, options, true)
+    }
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> ReadOnlyDb<T> {
+        Self::open_read_only_with_options(path, DbOptions::default())
+    }
+    pub fn open_read_only_with_options<P: AsRef<Path>>(
+        path: P,
+        options: DbOptions,
+    ) -> ReadOnlyDb<T> {
+        ReadOnlyDb {
+            db: Self::from_rocksdb(
+                rocksdb::DB::open_for_read_only(&db_opts(&options), path, false).unwrap(),
+                options,
+                false,
+            ),
+        }
+    }
+    pub fn open_as_secondary<P: AsRef<Path>, Q: AsRef<Path>>(
+        primary_path: P,
+        secondary_path: Q,
+    ) -> ReadOnlyDb<T> {
+        Self::open_as_secondary_with_options(primary_path, secondary_path, DbOptions::default())
+    }
+    pub fn open_as_secondary_with_options<P: AsRef<Path>, Q: AsRef<Path>>(
+        primary_path: P,
+        secondary_path: Q,
+        options: DbOptions,
+    ) -> ReadOnlyDb<T> {
+        let mut opts = db_opts(&options);
+        opts.set_max_open_files(-1);
+        ReadOnlyDb {
+            db: Self::from_rocksdb(
+                rocksdb::DB::open_as_secondary(
+                    &opts,
+                    primary_path.as_ref(),
+                    secondary_path.as_ref(),
+                )
+                .unwrap(),
+                options,
+                false,
+            ),
+        }
+    }
+    pub fn restore_from<P: AsRef<Path>, Q: AsRef<Path>>(backup_dir: P, path: Q) -> Self {
+        let mut engine = BackupEngine::open(
+            &BackupEngineOptions::new(backup_dir).unwrap(),
+            &rocksdb::Env::new().unwrap(),
+        )
+        .unwrap();
+        engine
+            .restore_from_latest_backup(&path, &path, &RestoreOptions::default())
+            .unwrap();
+        Self::open(path)
+    }
+    pub fn checkpoint<P: AsRef<Path>>(&self, path: P) {
+        let _guard = self.root.read_blocking();
+        Checkpoint::new(&self.inner.rocksdb)
+            .unwrap()
+            .create_checkpoint(path)
+            .unwrap();
+    }
+    pub fn backup_to<P: AsRef<Path>>(&self, backup_dir: P) {
+        let _guard = self.root.read_blocking();
+        let mut engine = BackupEngine::open(
+            &BackupEngineOptions::new(backup_dir).unwrap(),
+            &rocksdb::Env::new().unwrap(),
+        )
+        .unwrap();
+        engine
+            .create_new_backup_flush(&self.inner.rocksdb, true)
+            .unwrap();
+    }
+    pub fn export_json<W: Write>(&self, writer: W) -> serde_json::Result<()> {
+        json::export(&*self.r(), writer)
+    }
+    /// Maps are staged on disk while the document is read and copied into place by a single
+    /// spilling transaction, so the document does not need to fit in memory.
+    pub fn import_json<R: Read>(&self, reader: R) -> serde_json::Result<()> {
+        let read = self.r();
+        let root = json::import(&self.inner, reader)?;
+        drop(read);
+        *self.w_spilling() = root;
+        Ok(())
+    }
+    pub fn verify(&self) -> VerifyReport {
+        Verifier::run(&*self.r(), &self.inner)
+    }
+    /// Undecodable keys are left in place and listed in `unrepairable_keys`.
+    pub fn repair(&self) -> VerifyReport {
+        let mut write = self.w();
+        let mut report = Verifier::run(&*write, &self.inner);
+        for db_key in report.orphaned_keys.iter().chain(&report.mismatched_keys) {
+            write.batch().delete_raw(db_key);
+        }
+        report.unrepairable_keys = report.undecodable_keys.clone();
+        report
+    }
+    /// Rewrites values sealed with `previous_keys` and map keys sealed with `previous_map_key`
+    /// in a single spilling transaction. Counter merge operands are folded into their values by
+    /// a full compaction first.
+    #[cfg(feature = "encryption")]
+    pub fn reencrypt(&self) -> usize {
+        let db = &self.inner;
+        let mut write = self.w_spilling();
+        let mut compact_opts = rocksdb::CompactOptions::default();
+        compact_opts.set_bottommost_level_compaction(rocksdb::BottommostLevelCompaction::Force);
+        db.rocksdb
+            .compact_range_opt(None::<&[u8]>, None::<&[u8]>, &compact_opts);
+        let renamed_prefixes = Verifier::renamed_prefixes(&*write);
+        let mut rewritten = 0;
+        for (db_key, stored) in LockContext::raw_iter(&Prefix::root(db), (vec![], None)) {
+            let moved = Prefix::split_leaf(&db_key).and_then(|(prefix, map_key)| {
+                let renamed = Prefix {
+                    key: renamed_prefixes.get(prefix)?.clone(),
+                    ..Prefix::root(db)
+                };
+                let leaf = renamed.stored_leaf(&db.codec.reseal_map_key(map_key)?);
+                (leaf != *db_key).then_some(leaf)
+            });
+            let resealed = match db.codec.unseal(&stored) {
+                Some((plain, true)) => Some(db.codec.encode_value(plain.into_owned())),
+                _ => None,
+            };
+            if moved.is_none() && resealed.is_none() {
+                continue;
+            }
+            let encoded = resealed.as_deref().unwrap_or(&stored);
+            if moved.is_some() {
+                write.batch().delete_raw(&db_key);
+            }
+            write
+                .batch()
+                .put_raw(moved.as_deref().unwrap_or(&db_key), encoded);
+            rewritten += 1;
         }
+        rewritten
+    }
+    pub fn flush(&self) {
+        self.inner.rocksdb.flush().unwrap();
+    }
+    pub fn stats(&self) -> DbStats {
+        self.inner.stats()
+    }
+    pub fn property_value(&self, name: &str) -> Option<String> {
+        self.inner.rocksdb.property_value(name).unwrap()
+    }
+    pub fn property_int_value(&self, name: &str) -> Option<u64> {
+        self.inner.rocksdb.property_int_value(name).unwrap()
     }
     pub fn r(&self) -> RGuard<'_, T> {
         RGuard::new(self)
@@ -93,4 +339,50 @@ impl<T: BigObject + Default> Db<T> {
     pub fn w(&self) -> WGuard<'_, T> {
         WGuard::new(self)
     }
+    pub fn sweep_every<F: Fn(&mut T) + Send + 'static>(
+        self: &Arc<Self>,
+        interval: Duration,
+        sweep: F,
+    ) {
+        let db = Arc::downgrade(self);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            let Some(db) = db.upgrade() else {
+                break;
+            };
+            sweep(&mut db.w());
+        });
+    }
+    pub fn w_spilling(&self) -> WGuard<'_, T> {
+        WGuard::new_spilling(self)
+    }
+    pub async fn read_async(&self) -> RGuard<'_, T> {
+        RGuard::new_async(self).await
+    }
+    pub async fn write_async(&self) -> AsyncWGuard<'_, T> {
+        AsyncWGuard::new(self).await
+    }
+}
+
+pub struct ReadOnlyDb<T: BigObject> {
+    db: Db<T>,
+}
+
+impl<T: BigObject + Default> ReadOnlyDb<T> {
+    pub fn r(&self) -> RGuard<'_, T> {
+        RGuard::new(&self.db)
+    }
+    pub async fn read_async(&self) -> RGuard<'_, T> {
+        RGuard::new_async(&self.db).await
+    }
+    pub fn stats(&self) -> DbStats {
+        self.db.stats()
+    }
+    pub fn try_catch_up_with_primary(&self) {
+        let mut root = self.db.root.write_blocking();
+        let inner = &self.db.inner;
+        inner.rocksdb.try_catch_up_with_primary().unwrap();
+        inner.cache.invalidate_all();
+        *root = load_root(inner);
+    }
 }
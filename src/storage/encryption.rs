@@ -0,0 +1,81 @@
+use std::fmt;
+
+use aes_gcm_siv::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256GcmSiv, Nonce,
+};
+
+const NONCE_LEN: usize = 12;
+
+#[derive(Clone)]
+pub struct Encryption {
+    pub key: [u8; 32],
+    pub previous_keys: Vec<[u8; 32]>,
+    pub map_key: Option<[u8; 32]>,
+    pub previous_map_key: Option<[u8; 32]>,
+}
+
+impl fmt::Debug for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Encryption")
+            .field("previous_keys", &self.previous_keys.len())
+            .field("map_key", &self.map_key.is_some())
+            .field("previous_map_key", &self.previous_map_key.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+pub(crate) struct Ciphers {
+    current: Aes256GcmSiv,
+    previous: Vec<Aes256GcmSiv>,
+    map_key: Option<Aes256GcmSiv>,
+    previous_map_key: Option<Aes256GcmSiv>,
+}
+
+impl Ciphers {
+    pub(crate) fn new(encryption: &Encryption) -> Self {
+        let cipher = |key: &[u8; 32]| Aes256GcmSiv::new(key.into());
+        Self {
+            current: cipher(&encryption.key),
+            previous: encryption.previous_keys.iter().map(cipher).collect(),
+            map_key: encryption.map_key.as_ref().map(cipher),
+            previous_map_key: encryption.previous_map_key.as_ref().map(cipher),
+        }
+    }
+
+    pub(crate) fn encrypt(&self, plain: &[u8], out: &mut Vec<u8>) {
+        let nonce = Aes256GcmSiv::generate_nonce(&mut OsRng);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&self.current.encrypt(&nonce, plain).unwrap());
+    }
+
+    pub(crate) fn decrypt(&self, sealed: &[u8]) -> Option<(Vec<u8>, bool)> {
+        if sealed.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::from_slice(nonce);
+        if let Ok(plain) = self.current.decrypt(nonce, ciphertext) {
+            return Some((plain, true));
+        }
+        self.previous
+            .iter()
+            .find_map(|cipher| cipher.decrypt(nonce, ciphertext).ok())
+            .map(|plain| (plain, false))
+    }
+
+    pub(crate) fn map_key(&self) -> Option<&Aes256GcmSiv> {
+        self.map_key.as_ref()
+    }
+
+    pub(crate) fn decrypt_map_key(&self, stored: &[u8]) -> Option<Vec<u8>> {
+        self.map_key
+            .iter()
+            .chain(&self.previous_map_key)
+            .find_map(|cipher| cipher.decrypt(&Nonce::default(), stored).ok())
+    }
+}
+
+pub(crate) fn encrypt_map_key(cipher: &Aes256GcmSiv, plain: &[u8]) -> Vec<u8> {
+    cipher.encrypt(&Nonce::default(), plain).unwrap()
+}
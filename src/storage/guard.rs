@@ -1,10 +1,10 @@
 use std::{
-    cell::{Ref, RefCell},
-    mem::swap,
+    mem::{swap, take},
     ops::{Deref, DerefMut},
+    sync::Arc,
 };
 
-use parking_lot::{RwLockReadGuard, RwLockUpgradableReadGuard};
+use async_lock::{RwLockReadGuard, RwLockUpgradableReadGuard};
 
 use crate::{
     bigobject::BigObject,
@@ -12,24 +12,31 @@ use crate::{
         batch::Batch,
         db::{Db, DbInner},
         lock_context::LockContext,
+        options::Durability,
         prefix::Prefix,
+        staging::Staging,
+        trace,
     },
 };
 
 pub struct RGuard<'a, T: BigObject> {
-    _guard: RwLockReadGuard<'a, DbInner>,
+    root: RwLockReadGuard<'a, T>,
     _context: LockContext,
-    root: Ref<'a, T>,
 }
 
 impl<'a, T: BigObject> RGuard<'a, T> {
     pub(super) fn new(db: &'a Db<T>) -> RGuard<'a, T> {
-        let guard = db.inner.read();
-        let context = LockContext::new(&guard);
+        let guard = trace::lock("read", || db.root.read_blocking());
+        Self::with_guard(db, guard)
+    }
+    pub(super) async fn new_async(db: &'a Db<T>) -> RGuard<'a, T> {
+        let guard = trace::lock_async("read", db.root.read()).await;
+        Self::with_guard(db, guard)
+    }
+    fn with_guard(db: &'a Db<T>, guard: RwLockReadGuard<'a, T>) -> RGuard<'a, T> {
         RGuard {
-            _guard: guard,
-            _context: context,
-            root: db.root.borrow(),
+            root: guard,
+            _context: LockContext::new(&db.inner),
         }
     }
 }
@@ -42,24 +49,75 @@ impl<'a, T: BigObject> Deref for RGuard<'a, T> {
     }
 }
 
-pub struct WGuard<'a, T: BigObject> {
-    guard: Option<RwLockUpgradableReadGuard<'a, DbInner>>,
+struct Writer<'a, T: BigObject> {
+    guard: Option<RwLockUpgradableReadGuard<'a, T>>,
     _context: LockContext,
+    batch: Batch,
     root: T,
-    db_root: &'a RefCell<T>,
+    db: &'a Arc<DbInner>,
 }
 
-impl<'a, T: BigObject> WGuard<'a, T> {
-    pub(super) fn new(db: &'a Db<T>) -> WGuard<'a, T> {
-        let guard = db.inner.upgradable_read();
-        let context = LockContext::new(&guard);
-        let root = db.root.borrow().big_clone();
-        WGuard {
+impl<'a, T: BigObject> Writer<'a, T> {
+    fn new(db: &'a Db<T>, guard: RwLockUpgradableReadGuard<'a, T>) -> Writer<'a, T> {
+        let context = LockContext::new(&db.inner);
+        let root = guard.big_clone();
+        Writer {
             guard: Some(guard),
             _context: context,
+            batch: Batch::default(),
             root,
-            db_root: &db.root,
+            db: &db.inner,
+        }
+    }
+    fn finalize(&mut self) -> Batch {
+        trace::span!("bigobject::finalize");
+        let mut batch = take(&mut self.batch);
+        let mut prefix = Prefix::root(self.db);
+        self.root.finalize(|| &mut prefix, &mut batch);
+        let encoded = self
+            .db
+            .codec
+            .encode_value(rmp_serde::to_vec(&self.root).unwrap());
+        batch.put_raw(&[0], &encoded);
+        batch
+    }
+    fn apply(&mut self, batch: Batch, db_root: &mut T) {
+        if batch.is_spilling() {
+            let mut prefix = Prefix::root(self.db);
+            self.root.initialize(|| &mut prefix);
         }
+        batch.apply(self.db);
+        swap(db_root, &mut self.root);
+    }
+}
+
+pub struct WGuard<'a, T: BigObject> {
+    writer: Writer<'a, T>,
+}
+
+impl<'a, T: BigObject> WGuard<'a, T> {
+    pub(super) fn new(db: &'a Db<T>) -> WGuard<'a, T> {
+        let guard = trace::lock("write", || db.root.upgradable_read_blocking());
+        WGuard {
+            writer: Writer::new(db, guard),
+        }
+    }
+    pub(super) fn new_spilling(db: &'a Db<T>) -> WGuard<'a, T> {
+        let mut write = Self::new(db);
+        let staging = Arc::new(Staging::create(&db.inner));
+        let mut prefix = Prefix {
+            staging: Some(staging.clone()),
+            ..Prefix::root(&db.inner)
+        };
+        write.writer.root.initialize(|| &mut prefix);
+        write.writer.batch = Batch::spilling(staging);
+        write
+    }
+    pub fn set_durability(&mut self, durability: Durability) {
+        self.writer.batch.set_durability(durability);
+    }
+    pub(super) fn batch(&mut self) -> &mut Batch {
+        &mut self.writer.batch
     }
 }
 
@@ -67,13 +125,13 @@ impl<'a, T: BigObject> Deref for WGuard<'a, T> {
     type Target = T;
 
     fn deref(&self) -> &T {
-        &self.root
+        &self.writer.root
     }
 }
 
 impl<'a, T: BigObject> DerefMut for WGuard<'a, T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.root
+        &mut self.writer.root
     }
 }
 
@@ -82,14 +140,50 @@ impl<'a, T: BigObject> Drop for WGuard<'a, T> {
         if std::thread::panicking() {
             return;
         }
-        let mut batch = Batch::default();
-        let mut prefix = Prefix::new();
-        self.root.finalize(|| &mut prefix, &mut batch);
-        batch
-            .rocksdb
-            .put([0], rmp_serde::to_vec(&self.root).unwrap());
-        let db = RwLockUpgradableReadGuard::upgrade(self.guard.take().unwrap());
-        batch.apply(&db);
-        swap(self.db_root.borrow_mut().deref_mut(), &mut self.root);
+        trace::span!("bigobject::commit");
+        let batch = self.writer.finalize();
+        let guard = self.writer.guard.take().unwrap();
+        let mut guard = trace::lock("commit", || {
+            RwLockUpgradableReadGuard::upgrade_blocking(guard)
+        });
+        self.writer.apply(batch, &mut guard);
+    }
+}
+
+/// Changes are only written by `commit_async`; dropping the guard discards them.
+pub struct AsyncWGuard<'a, T: BigObject> {
+    writer: Writer<'a, T>,
+}
+
+impl<'a, T: BigObject> AsyncWGuard<'a, T> {
+    pub(super) async fn new(db: &'a Db<T>) -> AsyncWGuard<'a, T> {
+        let guard = trace::lock_async("write", db.root.upgradable_read()).await;
+        AsyncWGuard {
+            writer: Writer::new(db, guard),
+        }
+    }
+    pub fn set_durability(&mut self, durability: Durability) {
+        self.writer.batch.set_durability(durability);
+    }
+    pub async fn commit_async(mut self) {
+        let batch = self.writer.finalize();
+        let guard = self.writer.guard.take().unwrap();
+        let mut guard =
+            trace::lock_async("commit", RwLockUpgradableReadGuard::upgrade(guard)).await;
+        self.writer.apply(batch, &mut guard);
+    }
+}
+
+impl<'a, T: BigObject> Deref for AsyncWGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.writer.root
+    }
+}
+
+impl<'a, T: BigObject> DerefMut for AsyncWGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.writer.root
     }
 }
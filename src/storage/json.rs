@@ -0,0 +1,89 @@
+use std::{
+    cell::RefCell,
+    io::{BufReader, BufWriter, Read, Write},
+    marker::PhantomData,
+    sync::Arc,
+};
+
+use serde::{de::DeserializeSeed, Deserializer};
+
+use crate::{
+    bigobject::BigObject,
+    storage::{db::DbInner, prefix::Prefix, staging::Staging},
+};
+
+// Big objects write their contents for human-readable formats and only their root fields for
+// the MessagePack encoding used in storage, so export needs no mode of its own.
+
+thread_local! {
+    static IMPORT: RefCell<Option<Import>> = const { RefCell::new(None) };
+}
+
+struct Import {
+    root: Prefix,
+    maps: u64,
+}
+
+/// Deserializes a document while the maps in it spill their entries in chunks to a staging
+/// area, each under a prefix of its own until the root is committed.
+struct ImportSeed<T> {
+    root: Prefix,
+    _root: PhantomData<T>,
+}
+
+impl<'a, T: BigObject> DeserializeSeed<'a> for ImportSeed<T> {
+    type Value = T;
+
+    fn deserialize<D: Deserializer<'a>>(self, deserializer: D) -> Result<T, D::Error> {
+        struct Done;
+        impl Drop for Done {
+            fn drop(&mut self) {
+                IMPORT.with(|import| import.take());
+            }
+        }
+        IMPORT.with(|import| {
+            let previous = import.replace(Some(Import {
+                root: self.root,
+                maps: 0,
+            }));
+            assert!(previous.is_none(), "Imports cannot be nested");
+        });
+        let _done = Done;
+        T::deserialize(deserializer)
+    }
+}
+
+/// A fresh prefix in the staging area of the running import, if any. The trailing field index
+/// keeps the index entries of a `BigIndexedMap` apart from other maps.
+pub(crate) fn import_prefix() -> Option<Prefix> {
+    IMPORT.with(|import| {
+        let mut import = import.borrow_mut();
+        let import = import.as_mut()?;
+        import.maps += 1;
+        let mut prefix = import.root.clone();
+        prefix.key.push(u8::MAX);
+        prefix.key.extend_from_slice(&import.maps.to_be_bytes());
+        prefix.key.push(1);
+        Some(prefix)
+    })
+}
+
+pub(super) fn export<T: BigObject, W: Write>(root: &T, writer: W) -> serde_json::Result<()> {
+    let mut writer = BufWriter::new(writer);
+    serde_json::to_writer_pretty(&mut writer, root)?;
+    writer.flush().map_err(serde_json::Error::io)
+}
+
+pub(super) fn import<T: BigObject, R: Read>(db: &Arc<DbInner>, reader: R) -> serde_json::Result<T> {
+    let seed = ImportSeed {
+        root: Prefix {
+            staging: Some(Arc::new(Staging::create(db))),
+            ..Prefix::root(db)
+        },
+        _root: PhantomData,
+    };
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+    let root = seed.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(root)
+}
@@ -1,102 +1,297 @@
-use std::{any::Any, cell::RefCell, marker::PhantomData, sync::Arc};
-
-use elsa::FrozenVec;
+use std::{
+    any::Any,
+    collections::{BTreeSet, HashMap},
+    iter::{Map, Peekable},
+    marker::PhantomData,
+    mem::take,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
 
 use crate::{
-    bigobject::{bigmap::KeyRef, BigObject},
+    bigobject::{
+        bigmap::{Key, KeyRef},
+        BigObject,
+    },
     storage::{
-        db::{CacheEntry, DbInner, SyncWrapper},
+        db::{CacheEntry, DbInner},
         prefix::Prefix,
+        staging::{Kv, StagedIter},
+        trace,
     },
 };
 
-pub type PhantomContext = PhantomData<*const ()>;
-struct LockContextInner<'a> {
-    db: &'a DbInner,
-    read_stash: FrozenVec<Arc<dyn Any + Send + Sync>>,
-}
+const STASH_SHARDS: usize = 16;
+
+static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
 
 thread_local! {
-    static LOCK_CONTEXT: RefCell<Option<&'static LockContextInner<'static>>> = RefCell::new(None);
+    static STASH_SHARD: usize = NEXT_SHARD.fetch_add(1, Ordering::Relaxed) % STASH_SHARDS;
+}
+
+type Stashed = Arc<dyn Any + Send + Sync>;
+
+struct Shard {
+    min_tag: u64,
+    values: HashMap<usize, (u64, Stashed)>,
+}
+
+impl Default for Shard {
+    fn default() -> Self {
+        Self {
+            min_tag: u64::MAX,
+            values: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ReadStash {
+    next_epoch: AtomicU64,
+    guards: AtomicUsize,
+    active: Mutex<BTreeSet<u64>>,
+    shards: [Mutex<Shard>; STASH_SHARDS],
+}
+
+impl ReadStash {
+    fn enter(&self) -> u64 {
+        let mut active = self.active.lock().unwrap();
+        let epoch = self.next_epoch.fetch_add(1, Ordering::SeqCst);
+        active.insert(epoch);
+        self.guards.fetch_add(1, Ordering::SeqCst);
+        epoch
+    }
+
+    fn exit(&self, epoch: u64) {
+        let oldest = {
+            let mut active = self.active.lock().unwrap();
+            assert!(active.remove(&epoch));
+            self.guards.fetch_sub(1, Ordering::SeqCst);
+            active.first().copied().unwrap_or(u64::MAX)
+        };
+        for shard in &self.shards {
+            let released: HashMap<_, _> = {
+                let mut shard = shard.lock().unwrap();
+                if shard.min_tag > oldest {
+                    continue;
+                }
+                let (released, kept) = take(&mut shard.values)
+                    .into_iter()
+                    .partition(|(_, (tag, _))| *tag <= oldest);
+                shard.values = kept;
+                shard.min_tag = shard
+                    .values
+                    .values()
+                    .map(|(tag, _)| *tag)
+                    .min()
+                    .unwrap_or(u64::MAX);
+                released
+            };
+            drop(released);
+        }
+    }
+
+    fn assert_guarded(&self) {
+        assert!(
+            self.guards.load(Ordering::SeqCst) > 0,
+            "Big objects can only be read while a guard is held"
+        );
+    }
+
+    fn push<T: BigObject>(&self, value: Stashed) -> &'static T {
+        self.assert_guarded();
+        let value_ref = value.downcast_ref::<T>().unwrap() as *const T;
+        let tag = self.next_epoch.load(Ordering::SeqCst);
+        let shard = STASH_SHARD.with(|shard| *shard);
+        let mut shard = self.shards[shard].lock().unwrap();
+        shard.min_tag = shard.min_tag.min(tag);
+        let stashed = shard
+            .values
+            .entry(value_ref as *const () as usize)
+            .or_insert((tag, value));
+        stashed.0 = stashed.0.max(tag);
+        unsafe { &*value_ref }
+    }
 }
 
 pub struct LockContext {
-    _inner: Box<LockContextInner<'static>>,
-    _phantom: PhantomContext,
+    db: Arc<DbInner>,
+    epoch: u64,
 }
 
 impl LockContext {
-    pub(super) fn new(db: &DbInner) -> Self {
-        let inner = Box::new(LockContextInner {
-            db: unsafe { std::mem::transmute(db) },
-            read_stash: FrozenVec::new(),
-        });
-        LOCK_CONTEXT.with(|context| {
-            assert!(context
-                .replace(Some(unsafe { std::mem::transmute(inner.as_ref()) }))
-                .is_none())
-        });
+    pub(super) fn new(db: &Arc<DbInner>) -> Self {
         Self {
-            _inner: inner,
-            _phantom: PhantomContext::default(),
+            db: db.clone(),
+            epoch: db.read_stash.enter(),
         }
     }
 
-    pub fn last_key() -> Option<Vec<u8>> {
-        LOCK_CONTEXT.with(|context| {
-            context
-                .borrow_mut()
-                .unwrap()
-                .db
-                .rocksdb
-                .iterator(rocksdb::IteratorMode::End)
-                .next()
-                .map(|kv| kv.unwrap().0.into_vec())
-        })
+    pub fn get<T: BigObject, K: KeyRef>(prefix: &Prefix, key: &K) -> Option<&'static T> {
+        let db = &prefix.db();
+        let mut key_prefix = prefix.clone();
+        let prefix_len = key_prefix.append_map_key(key);
+        let db_key = key_prefix.into_leaf(prefix_len);
+        if let Some(staging) = &prefix.staging {
+            return db.stash(decode::<T>(
+                prefix,
+                &db_key,
+                staging.get(db, &db_key).as_deref(),
+            ));
+        }
+        db.stash(db.cached(&db_key, || {
+            decode::<T>(
+                prefix,
+                &db_key,
+                db.rocksdb.get_pinned(&db_key).unwrap().as_deref(),
+            )
+        }))
     }
 
-    pub fn get<T: BigObject, K: KeyRef>(prefix: &Prefix, key: &K) -> Option<&'static T> {
-        LOCK_CONTEXT.with(|context| {
-            let context = context.borrow_mut().unwrap();
-            let mut key_prefix = prefix.clone();
-            let prefix_len = key_prefix.append_map_key(key);
-            let db_key = key_prefix.into_leaf(prefix_len);
-            context
-                .db
-                .cache
-                .get_with_by_ref(&db_key, || {
-                    if let Some(encoded) = context.db.rocksdb.get_pinned(&db_key).unwrap() {
-                        let mut value = rmp_serde::decode::from_slice::<T>(&encoded).unwrap();
-                        let mut key_prefix = Prefix::from_leaf(db_key.clone(), prefix_len);
-                        value.initialize(|| &mut key_prefix);
-                        CacheEntry {
-                            len: (key_prefix.len() + encoded.len()).try_into().unwrap(),
-                            value: Some(Arc::new(SyncWrapper(value))),
-                        }
-                    } else {
-                        CacheEntry {
-                            len: db_key.len().try_into().unwrap(),
-                            value: None,
-                        }
-                    }
-                })
-                .value
-                .map(|value| {
-                    &context
-                        .read_stash
-                        .push_get(value)
-                        .downcast_ref::<SyncWrapper<T>>()
-                        .unwrap()
-                        .0
-                })
-        })
+    pub async fn get_async<T: BigObject, K: KeyRef>(
+        prefix: &Prefix,
+        key: &K,
+    ) -> Option<&'static T> {
+        if prefix.staging.is_some() {
+            return Self::get(prefix, key);
+        }
+        let db = &prefix.db();
+        let mut key_prefix = prefix.clone();
+        let prefix_len = key_prefix.append_map_key(key);
+        let db_key = key_prefix.into_leaf(prefix_len);
+        if let Some(entry) = db.cache.get(&db_key) {
+            db.stats.record_cache_lookup(true);
+            return db.stash(entry);
+        }
+        let read_db = db.clone();
+        let read_key = db_key.clone();
+        let encoded = blocking::unblock(move || read_db.rocksdb.get(read_key).unwrap()).await;
+        db.stash(db.cached(&db_key, || decode::<T>(prefix, &db_key, encoded.as_deref())))
+    }
+
+    pub fn iter<K: Key, T: BigObject>(prefix: &Prefix, from: Option<&K>) -> MapIter<K, T> {
+        MapIter {
+            iter: Self::raw_iter(prefix, prefix.leaf_range_from(from)),
+            prefix: prefix.clone(),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn raw_iter(prefix: &Prefix, (from, to): (Vec<u8>, Option<Vec<u8>>)) -> RawIter {
+        let staged = prefix
+            .staging
+            .as_ref()
+            .map(|staging| staging.iter((from.clone(), to.clone())));
+        let mut opts = rocksdb::ReadOptions::default();
+        opts.set_total_order_seek(true);
+        opts.set_iterate_lower_bound(from);
+        if let Some(to) = to {
+            opts.set_iterate_upper_bound(to);
+        }
+        let db = prefix.db();
+        db.read_stash.assert_guarded();
+        let iter = db.rocksdb.iterator_opt(rocksdb::IteratorMode::Start, opts);
+        RawIter {
+            iter: unsafe {
+                std::mem::transmute::<
+                    rocksdb::DBIteratorWithThreadMode<'_, rocksdb::DB>,
+                    rocksdb::DBIteratorWithThreadMode<'static, rocksdb::DB>,
+                >(iter)
+            }
+            .map(Result::unwrap as UnwrapKv)
+            .peekable(),
+            staged,
+            _prefix: prefix.clone(),
+            db,
+        }
+    }
+}
+
+impl DbInner {
+    fn cached(&self, db_key: &[u8], init: impl FnOnce() -> CacheEntry) -> CacheEntry {
+        let mut hit = true;
+        let entry = self.cache.get_with_by_ref(db_key, || {
+            hit = false;
+            trace::span!("bigobject::cache_miss", key = %trace::key_path(db_key));
+            init()
+        });
+        self.stats.record_cache_lookup(hit);
+        entry
+    }
+
+    fn stash<T: BigObject>(&self, entry: CacheEntry) -> Option<&'static T> {
+        entry.value.map(|value| self.read_stash.push(value))
+    }
+}
+
+fn decode<T: BigObject>(prefix: &Prefix, db_key: &[u8], encoded: Option<&[u8]>) -> CacheEntry {
+    if let Some(encoded) = encoded {
+        let mut value =
+            rmp_serde::decode::from_slice::<T>(&prefix.db().codec.decode_value(encoded)).unwrap();
+        let mut key_prefix = prefix.value_prefix(db_key.to_vec());
+        value.initialize(|| &mut key_prefix);
+        CacheEntry {
+            len: (key_prefix.len() + encoded.len()).try_into().unwrap(),
+            value: Some(Arc::new(value)),
+        }
+    } else {
+        CacheEntry {
+            len: db_key.len().try_into().unwrap(),
+            value: None,
+        }
+    }
+}
+
+type UnwrapKv = fn(Result<Kv, rocksdb::Error>) -> Kv;
+
+pub struct RawIter {
+    iter: Peekable<Map<rocksdb::DBIteratorWithThreadMode<'static, rocksdb::DB>, UnwrapKv>>,
+    staged: Option<StagedIter>,
+    _prefix: Prefix,
+    db: Arc<DbInner>,
+}
+
+impl Iterator for RawIter {
+    type Item = Kv;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.staged {
+            Some(staged) => staged.merge_next(&self.db.codec, &mut self.iter),
+            None => self.iter.next(),
+        }
+    }
+}
+
+pub struct MapIter<K: Key, T: BigObject> {
+    iter: RawIter,
+    prefix: Prefix,
+    _phantom: PhantomData<(K, T)>,
+}
+
+impl<K: Key, T: BigObject> Iterator for MapIter<K, T> {
+    type Item = (K, &'static T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (db_key, encoded) = self.iter.next()?;
+        let prefix = &self.prefix;
+        let db = &prefix.db();
+        let key = db
+            .codec
+            .decode_map_key(Prefix::split_leaf(&db_key).unwrap().1)
+            .unwrap();
+        let value = if prefix.staging.is_some() {
+            db.stash(decode::<T>(prefix, &db_key, Some(&encoded)))
+        } else {
+            db.stash(db.cached(&db_key, || decode::<T>(prefix, &db_key, Some(&encoded))))
+        };
+        Some((key, value.unwrap()))
     }
 }
 
 impl Drop for LockContext {
     fn drop(&mut self) {
-        LOCK_CONTEXT.with(|context| {
-            assert!(context.replace(None).is_some());
-        })
+        self.db.read_stash.exit(self.epoch);
     }
 }
@@ -0,0 +1,79 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "encryption")]
+use crate::storage::encryption::Encryption;
+use crate::storage::{compression::Compression, trace};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Durability {
+    #[default]
+    Buffered,
+    Sync,
+    GroupCommit(Duration),
+    NoWal,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DbOptions {
+    pub durability: Durability,
+    pub compression: Option<Compression>,
+    #[cfg(feature = "encryption")]
+    pub encryption: Option<Encryption>,
+}
+
+pub(crate) struct WalSync {
+    last_sync: Mutex<Instant>,
+    pending: AtomicBool,
+}
+
+impl Default for WalSync {
+    fn default() -> Self {
+        Self {
+            last_sync: Mutex::new(Instant::now()),
+            pending: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Durability {
+    pub(crate) fn write_opts(self) -> rocksdb::WriteOptions {
+        let mut opts = rocksdb::WriteOptions::default();
+        match self {
+            Durability::Sync => opts.set_sync(true),
+            Durability::NoWal => opts.disable_wal(true),
+            Durability::Buffered | Durability::GroupCommit(_) => {}
+        }
+        opts
+    }
+}
+
+impl WalSync {
+    pub(crate) fn after_write(&self, rocksdb: &rocksdb::DB, durability: Durability) {
+        let Durability::GroupCommit(interval) = durability else {
+            return;
+        };
+        self.pending.store(true, Ordering::Release);
+        if let Err(err) = self.sync_if_due(rocksdb, interval) {
+            trace::warning!("Failed to sync the WAL: {err}");
+        }
+    }
+    pub(crate) fn sync_if_due(
+        &self,
+        rocksdb: &rocksdb::DB,
+        interval: Duration,
+    ) -> Result<(), rocksdb::Error> {
+        let mut last_sync = self.last_sync.lock().unwrap();
+        if self.pending.load(Ordering::Acquire) && last_sync.elapsed() >= interval {
+            self.pending.store(false, Ordering::Release);
+            rocksdb.flush_wal(true)?;
+            *last_sync = Instant::now();
+        }
+        Ok(())
+    }
+}
@@ -1,91 +1,183 @@
-use std::io::Write;
+use std::sync::{Arc, Weak};
 
-use crate::{bigobject::bigmap::KeyRef, storage::lock_context::LockContext};
+use crate::{
+    bigobject::bigmap::KeyRef,
+    storage::{db::DbInner, staging::Staging},
+};
 
-pub struct Prefix(pub(crate) Vec<u8>);
+pub struct Prefix {
+    pub(crate) key: Vec<u8>,
+    pub(crate) db: Weak<DbInner>,
+    pub(crate) staging: Option<Arc<Staging>>,
+    pub(crate) relocation: Option<Arc<(Vec<u8>, Vec<u8>)>>,
+}
 
 impl Prefix {
     pub fn push_field_index(&mut self) {
-        self.0.push(0);
+        self.key.push(0);
     }
     pub fn set_field_index(&mut self, index: u8) {
-        *self.0.last_mut().unwrap() = index;
+        *self.key.last_mut().unwrap() = index;
     }
     pub fn pop_field_index(&mut self) {
-        self.0.pop();
+        self.key.pop();
     }
-    pub(crate) fn new() -> Self {
-        Self(Vec::new())
+    pub(crate) fn root(db: &Arc<DbInner>) -> Self {
+        Self {
+            key: Vec::new(),
+            db: Arc::downgrade(db),
+            staging: None,
+            relocation: None,
+        }
     }
     pub(crate) fn clone(&self) -> Self {
-        Self(self.0.clone())
+        Self {
+            key: self.key.clone(),
+            db: self.db.clone(),
+            staging: self.staging.clone(),
+            relocation: self.relocation.clone(),
+        }
+    }
+    pub(crate) fn relocated_from(&self, from: &Prefix) -> Self {
+        Self {
+            relocation: Some(Arc::new((from.key.clone(), self.key.clone()))),
+            ..self.clone()
+        }
+    }
+    pub(crate) fn without_relocation(&self) -> Self {
+        Self {
+            relocation: None,
+            ..self.clone()
+        }
+    }
+    pub(crate) fn is_location_of(&self, stored: &Prefix) -> bool {
+        self.key == stored.key || self.is_relocation_of(stored)
+    }
+    pub(crate) fn is_relocation_of(&self, stored: &Prefix) -> bool {
+        self.relocation.as_ref().is_some_and(|relocation| {
+            let (from, to) = &**relocation;
+            stored
+                .key
+                .strip_prefix(&from[..])
+                .is_some_and(|rest| self.key.strip_prefix(&to[..]) == Some(rest))
+        })
+    }
+    pub(crate) fn db(&self) -> Arc<DbInner> {
+        self.db.upgrade().expect("Database is closed")
+    }
+    pub(crate) fn sorted(&self) -> bool {
+        self.db().codec.sorted_map_keys()
     }
     pub(crate) fn len(&self) -> usize {
-        self.0.len()
+        self.key.len()
     }
-    pub(crate) fn extract_prefix(key: &[u8]) -> &[u8] {
+    fn leaf_lens(key: &[u8]) -> Option<(usize, usize)> {
         let len = key.len();
-        if len == 0 {
-            return key;
-        }
-        match key[len - 1] {
-            0x0..=0x7F => {
-                let prefix = key[len - 1] as usize;
-                &key[..prefix]
-            }
-            0x80..=0xBF => {
-                let prefix =
-                    u16::from_le_bytes(key[len - 2..].try_into().unwrap()) as usize & !0x8000;
-                &key[..prefix]
-            }
-            0xC0..=0xDF => {
-                let prefix =
-                    u32::from_le_bytes(key[len - 4..].try_into().unwrap()) as usize & !0xC0000000;
-                &key[..prefix]
-            }
-            _ => unreachable!(),
-        }
+        let (prefix_len, suffix_len) = match *key.last()? {
+            last @ 0x0..=0x7F => (last as usize, 1),
+            0x80..=0xBF if len >= 2 => (
+                u16::from_le_bytes(key[len - 2..].try_into().unwrap()) as usize & !0x8000,
+                2,
+            ),
+            0xC0..=0xDF if len >= 4 => (
+                u32::from_le_bytes(key[len - 4..].try_into().unwrap()) as usize & !0xC0000000,
+                4,
+            ),
+            _ => return None,
+        };
+        (prefix_len + suffix_len <= len).then_some((prefix_len, suffix_len))
+    }
+    pub(crate) fn extract_prefix(key: &[u8]) -> &[u8] {
+        Self::leaf_lens(key).map_or(key, |(prefix_len, _)| &key[..prefix_len])
+    }
+    pub fn split_leaf(key: &[u8]) -> Option<(&[u8], &[u8])> {
+        let (prefix_len, suffix_len) = Self::leaf_lens(key)?;
+        (prefix_len + suffix_len < key.len() && key[prefix_len] == 0).then(|| {
+            (
+                &key[..prefix_len],
+                &key[prefix_len + 1..key.len() - suffix_len],
+            )
+        })
     }
     pub(crate) fn append_map_key<K: KeyRef>(&mut self, map_key: &K) -> usize {
-        let prefix_len = self.0.len();
-        self.0.push(1);
-        storekey::serialize_into(self.0.by_ref(), map_key).unwrap();
+        let prefix_len = self.key.len();
+        self.key.push(1);
+        self.db().codec.encode_map_key(map_key, &mut self.key);
         prefix_len
     }
     pub(crate) fn next_prefix(&self) -> Prefix {
-        let next = if let Some(nonff) = self.0.iter().rposition(|&byte| byte < u8::MAX) {
-            let mut next = self.0[..nonff].to_vec();
+        let next = if let Some(nonff) = self.key.iter().rposition(|&byte| byte < u8::MAX) {
+            let mut next = self.key[..=nonff].to_vec();
             *next.last_mut().unwrap() += 1;
             next
-        } else if let Some(mut next) = LockContext::last_key() {
+        } else if let Some(kv) = self
+            .db()
+            .rocksdb
+            .iterator(rocksdb::IteratorMode::End)
+            .next()
+        {
+            let mut next = kv.unwrap().0.into_vec();
             next.push(0);
             next
         } else {
             vec![]
         };
-        Prefix(next)
+        Prefix {
+            key: next,
+            db: self.db.clone(),
+            staging: self.staging.clone(),
+            relocation: None,
+        }
+    }
+    pub(crate) fn leaf_range(&self) -> (Vec<u8>, Option<Vec<u8>>) {
+        let mut from = self.key.clone();
+        from.push(0);
+        if self.key.is_empty() {
+            from.push(0);
+        }
+        let mut to = self.key.clone();
+        to.push(1);
+        (from, Some(to))
+    }
+    pub(crate) fn leaf_range_from<K: KeyRef>(
+        &self,
+        map_key: Option<&K>,
+    ) -> (Vec<u8>, Option<Vec<u8>>) {
+        let (mut from, to) = self.leaf_range();
+        if let Some(map_key) = map_key {
+            from.truncate(self.key.len() + 1);
+            self.db().codec.encode_map_key(map_key, &mut from);
+        }
+        (from, to)
     }
     pub(crate) fn into_leaf(mut self, prefix_len: usize) -> Vec<u8> {
-        if prefix_len != self.0.len() {
-            self.0[prefix_len] = 0;
+        if prefix_len != self.key.len() {
+            self.key[prefix_len] = 0;
         }
         match prefix_len {
             0x0..=0x7F => {
-                self.0.push(prefix_len as u8);
+                self.key.push(prefix_len as u8);
             }
             0x80..=0x3FFF => {
                 let prefix_len = (prefix_len as u16) | 0x8000;
-                self.0.extend_from_slice(&prefix_len.to_le_bytes())
+                self.key.extend_from_slice(&prefix_len.to_le_bytes())
             }
             0x4000..=0x1FFFFFFF => {
                 let prefix_len = (prefix_len as u32) | 0xC0000000;
-                self.0.extend_from_slice(&prefix_len.to_le_bytes())
+                self.key.extend_from_slice(&prefix_len.to_le_bytes())
             }
             _ => unimplemented!("Database key is too big"),
         }
-        self.0
+        self.key
+    }
+    pub(crate) fn stored_leaf(&self, map_key: &[u8]) -> Vec<u8> {
+        let mut leaf = self.clone();
+        leaf.key.push(1);
+        leaf.key.extend_from_slice(map_key);
+        leaf.into_leaf(self.len())
     }
-    pub(crate) fn from_leaf(mut leaf: Vec<u8>, prefix_len: usize) -> Self {
+    pub(crate) fn value_prefix(&self, mut leaf: Vec<u8>) -> Self {
+        let prefix_len = self.len();
         leaf[prefix_len] = 1;
         match prefix_len {
             0x0..=0x7F => {
@@ -99,6 +191,22 @@ impl Prefix {
             }
             _ => unimplemented!("Database key is too big"),
         }
-        Self(leaf)
+        Self {
+            key: leaf,
+            db: self.db.clone(),
+            staging: self.staging.clone(),
+            relocation: self.relocation.clone(),
+        }
+    }
+    pub(crate) fn relocate_leaf(&self, leaf: &[u8], from_len: usize) -> Option<Vec<u8>> {
+        let (prefix_len, suffix_len) = Self::leaf_lens(leaf)?;
+        if prefix_len < from_len {
+            return None;
+        }
+        let mut prefix = self.without_relocation();
+        prefix
+            .key
+            .extend_from_slice(&leaf[from_len..leaf.len() - suffix_len]);
+        Some(prefix.into_leaf(prefix_len - from_len + self.len()))
     }
 }
@@ -0,0 +1,289 @@
+use std::{iter::Peekable, mem::ManuallyDrop, path::PathBuf};
+
+use crate::storage::{
+    bulk::{self, SstEntry},
+    codec::Codec,
+    db::DbInner,
+    trace,
+};
+
+const PUT: u8 = 0;
+const DELETE: u8 = 1;
+const MERGE: u8 = 2;
+
+const RANGES: &str = "ranges";
+
+pub(crate) const SPILL_CHANGES: usize = 1024;
+
+pub(crate) struct Staging {
+    rocksdb: ManuallyDrop<rocksdb::DB>,
+    path: PathBuf,
+}
+
+impl Staging {
+    pub(super) fn create(db: &DbInner) -> Self {
+        let path = bulk::unique_path(db, "staging");
+        let mut opts = rocksdb::Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
+        Self {
+            rocksdb: ManuallyDrop::new(rocksdb::DB::open_cf(&opts, &path, [RANGES]).unwrap()),
+            path,
+        }
+    }
+
+    fn write(&self, batch: rocksdb::WriteBatch) {
+        let mut opts = rocksdb::WriteOptions::default();
+        opts.disable_wal(true);
+        self.rocksdb.write_opt(batch, &opts).unwrap();
+    }
+
+    fn ranges(&self) -> &rocksdb::ColumnFamily {
+        self.rocksdb.cf_handle(RANGES).unwrap()
+    }
+
+    fn range_before(&self, db_key: &[u8]) -> Option<Kv> {
+        self.rocksdb
+            .iterator_cf(
+                self.ranges(),
+                rocksdb::IteratorMode::From(db_key, rocksdb::Direction::Reverse),
+            )
+            .next()
+            .map(Result::unwrap)
+    }
+
+    fn is_deleted(&self, db_key: &[u8]) -> bool {
+        self.range_before(db_key)
+            .is_some_and(|(_, to)| db_key < to.as_ref())
+    }
+
+    pub(super) fn put(&self, db_key: &[u8], encoded: &[u8]) {
+        let mut batch = rocksdb::WriteBatch::default();
+        batch.put(db_key, [&[PUT], encoded].concat());
+        self.write(batch);
+    }
+
+    pub(super) fn delete(&self, db_key: &[u8]) {
+        let mut batch = rocksdb::WriteBatch::default();
+        batch.put(db_key, [DELETE]);
+        self.write(batch);
+    }
+
+    pub(super) fn delete_range(&self, from: &[u8], to: &[u8]) {
+        let mut batch = rocksdb::WriteBatch::default();
+        batch.delete_range(from, to);
+        if from >= to {
+            self.write(batch);
+            return;
+        }
+        let (mut from, mut to) = (from.to_vec(), to.to_vec());
+        if let Some((start, end)) = self.range_before(&from) {
+            if from.as_slice() <= end.as_ref() {
+                from = start.into_vec();
+                to = to.max(end.into_vec());
+            }
+        }
+        let ranges = self.rocksdb.iterator_cf(
+            self.ranges(),
+            rocksdb::IteratorMode::From(&from, rocksdb::Direction::Forward),
+        );
+        for kv in ranges {
+            let (start, end) = kv.unwrap();
+            if to.as_slice() < start.as_ref() {
+                break;
+            }
+            batch.delete_cf(self.ranges(), &start);
+            to = to.max(end.into_vec());
+        }
+        batch.put_cf(self.ranges(), from, to);
+        self.write(batch);
+    }
+
+    pub(super) fn merge(&self, db: &DbInner, db_key: &[u8], delta: &[u8]) {
+        let staged = match self.rocksdb.get(db_key).unwrap() {
+            Some(staged) if staged[0] == PUT => {
+                [&[PUT], &sum_counters(&db.codec, [&staged[1..], delta])[..]].concat()
+            }
+            Some(staged) if staged[0] == DELETE => [&[PUT], delta].concat(),
+            Some(staged) => [
+                &[MERGE],
+                &sum_counters(&db.codec, [&staged[1..], delta])[..],
+            ]
+            .concat(),
+            None if self.is_deleted(db_key) => [&[PUT], delta].concat(),
+            None => [&[MERGE], delta].concat(),
+        };
+        let mut batch = rocksdb::WriteBatch::default();
+        batch.put(db_key, staged);
+        self.write(batch);
+    }
+
+    pub(crate) fn get(&self, db: &DbInner, db_key: &[u8]) -> Option<Vec<u8>> {
+        let stored = || {
+            if self.is_deleted(db_key) {
+                return None;
+            }
+            db.rocksdb.get(db_key).unwrap()
+        };
+        match self.rocksdb.get_pinned(db_key).unwrap() {
+            None => stored(),
+            Some(staged) => resolve(&db.codec, &staged, stored),
+        }
+    }
+
+    pub(super) fn iter(&self, (from, to): (Vec<u8>, Option<Vec<u8>>)) -> StagedIter {
+        let mut opts = rocksdb::ReadOptions::default();
+        opts.set_iterate_lower_bound(from.clone());
+        if let Some(to) = &to {
+            opts.set_iterate_upper_bound(to.clone());
+        }
+        let entries = self
+            .rocksdb
+            .iterator_opt(rocksdb::IteratorMode::Start, opts);
+        let mut opts = rocksdb::ReadOptions::default();
+        if let Some(to) = to {
+            opts.set_iterate_upper_bound(to);
+        }
+        let start = self
+            .range_before(&from)
+            .map_or(from, |(start, _)| start.into_vec());
+        let ranges = self.rocksdb.iterator_cf_opt(
+            self.ranges(),
+            opts,
+            rocksdb::IteratorMode::From(&start, rocksdb::Direction::Forward),
+        );
+        unsafe {
+            StagedIter {
+                entries: std::mem::transmute::<DbIter<'_>, DbIter<'static>>(entries).peekable(),
+                ranges: std::mem::transmute::<DbIter<'_>, DbIter<'static>>(ranges).peekable(),
+            }
+        }
+    }
+
+    pub(super) fn commit(&self, db: &DbInner, bulk_files: Vec<PathBuf>) -> (usize, usize) {
+        trace::span!("bigobject::commit_staged");
+        let (mut ops, mut bytes) = (0, 0);
+        let ranges = self
+            .rocksdb
+            .iterator_cf(self.ranges(), rocksdb::IteratorMode::Start)
+            .map(|kv| {
+                let (from, to) = kv.unwrap();
+                ops += 1;
+                bytes += from.len() + to.len();
+                (from.into_vec(), to.into_vec())
+            });
+        let mut files = bulk::write_range_deletions(db, ranges);
+        let deletes_ranges = !files.is_empty();
+        files.extend(bulk_files);
+        let entries = self
+            .rocksdb
+            .iterator(rocksdb::IteratorMode::Start)
+            .map(|kv| {
+                let (db_key, staged) = kv.unwrap();
+                ops += 1;
+                bytes += db_key.len() + staged.len();
+                let entry = match staged[0] {
+                    PUT => SstEntry::Put(staged[1..].to_vec()),
+                    DELETE => SstEntry::Delete,
+                    _ => SstEntry::Merge(staged[1..].to_vec()),
+                };
+                (db_key.into_vec(), entry)
+            });
+        files.extend(bulk::write_sst_files(db, entries));
+        bulk::ingest(db, files);
+        if deletes_ranges {
+            db.cache.invalidate_all();
+        } else {
+            for kv in self.rocksdb.iterator(rocksdb::IteratorMode::Start) {
+                db.cache.invalidate(kv.unwrap().0.as_ref());
+            }
+        }
+        (ops, bytes)
+    }
+}
+
+fn sum_counters<'a>(codec: &Codec, stored: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+    codec
+        .sum_counters(stored)
+        .expect("Failed to decode stored counter")
+}
+
+fn resolve(
+    codec: &Codec,
+    staged: &[u8],
+    stored: impl FnOnce() -> Option<Vec<u8>>,
+) -> Option<Vec<u8>> {
+    match staged[0] {
+        PUT => Some(staged[1..].to_vec()),
+        DELETE => None,
+        _ => Some(sum_counters(
+            codec,
+            stored().as_deref().into_iter().chain([&staged[1..]]),
+        )),
+    }
+}
+
+type DbIter<'a> = rocksdb::DBIteratorWithThreadMode<'a, rocksdb::DB>;
+
+pub(super) type Kv = (Box<[u8]>, Box<[u8]>);
+
+pub(super) struct StagedIter {
+    entries: Peekable<DbIter<'static>>,
+    ranges: Peekable<DbIter<'static>>,
+}
+
+impl StagedIter {
+    fn is_deleted(&mut self, db_key: &[u8]) -> bool {
+        while let Some(kv) = self.ranges.peek() {
+            let (from, to) = kv.as_ref().unwrap();
+            if db_key < to.as_ref() {
+                return from.as_ref() <= db_key;
+            }
+            self.ranges.next();
+        }
+        false
+    }
+
+    pub(super) fn merge_next(
+        &mut self,
+        codec: &Codec,
+        stored: &mut Peekable<impl Iterator<Item = Kv>>,
+    ) -> Option<Kv> {
+        loop {
+            while let Some((stored_key, _)) = stored.peek() {
+                if !self.is_deleted(stored_key) {
+                    break;
+                }
+                stored.next();
+            }
+            let staged_key = self.entries.peek().map(|kv| kv.as_ref().unwrap().0.clone());
+            let Some(staged_key) = staged_key else {
+                return stored.next();
+            };
+            let stored_value = match stored.peek() {
+                Some((stored_key, _)) if *stored_key < staged_key => return stored.next(),
+                Some((stored_key, _)) if *stored_key == staged_key => stored.next().map(|kv| kv.1),
+                _ => None,
+            };
+            let (db_key, staged) = self.entries.next().unwrap().unwrap();
+            if let Some(encoded) = resolve(codec, &staged, || {
+                stored_value.map(|value| value.into_vec())
+            }) {
+                return Some((db_key, encoded.into_boxed_slice()));
+            }
+        }
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        unsafe { ManuallyDrop::drop(&mut self.rocksdb) };
+        if let Err(err) = rocksdb::DB::destroy(&rocksdb::Options::default(), &self.path) {
+            trace::warning!(
+                "Failed to remove staging area {}: {err}",
+                self.path.display()
+            );
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct DbStats {
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub cache_evictions: u64,
+    pub cache_entries: u64,
+    pub cache_weighted_size: u64,
+    pub commits: u64,
+    pub commit_ops: u64,
+    pub commit_bytes: u64,
+    pub commit_time: Duration,
+    pub last_commit_ops: u64,
+    pub last_commit_bytes: u64,
+    pub last_commit_time: Duration,
+    pub max_commit_time: Duration,
+    pub estimate_num_keys: Option<u64>,
+    pub block_cache_usage: Option<u64>,
+}
+
+#[derive(Default)]
+pub(crate) struct Counters {
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    cache_evictions: AtomicU64,
+    commits: AtomicU64,
+    commit_ops: AtomicU64,
+    commit_bytes: AtomicU64,
+    commit_nanos: AtomicU64,
+    last_commit_ops: AtomicU64,
+    last_commit_bytes: AtomicU64,
+    last_commit_nanos: AtomicU64,
+    max_commit_nanos: AtomicU64,
+}
+
+impl Counters {
+    pub(super) fn record_cache_lookup(&self, hit: bool) {
+        if hit {
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.cache_misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    pub(super) fn record_eviction(&self) {
+        self.cache_evictions.fetch_add(1, Ordering::Relaxed);
+    }
+    pub(super) fn record_commit(&self, ops: usize, bytes: usize, time: Duration) {
+        let nanos = time.as_nanos().try_into().unwrap_or(u64::MAX);
+        self.commits.fetch_add(1, Ordering::Relaxed);
+        self.commit_ops.fetch_add(ops as u64, Ordering::Relaxed);
+        self.commit_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.commit_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.last_commit_ops.store(ops as u64, Ordering::Relaxed);
+        self.last_commit_bytes
+            .store(bytes as u64, Ordering::Relaxed);
+        self.last_commit_nanos.store(nanos, Ordering::Relaxed);
+        self.max_commit_nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+    pub(super) fn snapshot(&self) -> DbStats {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        DbStats {
+            cache_hits: load(&self.cache_hits),
+            cache_misses: load(&self.cache_misses),
+            cache_evictions: load(&self.cache_evictions),
+            commits: load(&self.commits),
+            commit_ops: load(&self.commit_ops),
+            commit_bytes: load(&self.commit_bytes),
+            commit_time: Duration::from_nanos(load(&self.commit_nanos)),
+            last_commit_ops: load(&self.last_commit_ops),
+            last_commit_bytes: load(&self.last_commit_bytes),
+            last_commit_time: Duration::from_nanos(load(&self.last_commit_nanos)),
+            max_commit_time: Duration::from_nanos(load(&self.max_commit_nanos)),
+            ..Default::default()
+        }
+    }
+}
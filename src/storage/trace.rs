@@ -0,0 +1,74 @@
+use std::future::Future;
+
+macro_rules! span {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($($arg)*).entered();
+    };
+}
+pub(crate) use span;
+
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::warn!($($arg)*);
+        #[cfg(not(feature = "tracing"))]
+        let _ = format_args!($($arg)*);
+    };
+}
+pub(crate) use warning;
+
+#[cfg(feature = "tracing")]
+pub(super) fn lock<G>(kind: &'static str, acquire: impl FnOnce() -> G) -> G {
+    let span =
+        tracing::debug_span!("bigobject::lock", kind, wait = tracing::field::Empty).entered();
+    let start = std::time::Instant::now();
+    let guard = acquire();
+    span.record("wait", tracing::field::debug(start.elapsed()));
+    guard
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(super) fn lock<G>(_kind: &'static str, acquire: impl FnOnce() -> G) -> G {
+    acquire()
+}
+
+#[cfg(feature = "tracing")]
+pub(super) async fn lock_async<G>(kind: &'static str, acquire: impl Future<Output = G>) -> G {
+    use tracing::Instrument;
+
+    let span = tracing::debug_span!("bigobject::lock", kind, wait = tracing::field::Empty);
+    let start = std::time::Instant::now();
+    let guard = acquire.instrument(span.clone()).await;
+    span.record("wait", tracing::field::debug(start.elapsed()));
+    guard
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(super) async fn lock_async<G>(_kind: &'static str, acquire: impl Future<Output = G>) -> G {
+    acquire.await
+}
+
+#[cfg(feature = "tracing")]
+pub(super) fn key_path(db_key: &[u8]) -> String {
+    use std::fmt::Write;
+
+    let hex = |bytes: &[u8]| {
+        bytes.iter().fold(String::new(), |mut out, byte| {
+            write!(out, "{byte:02x}").unwrap();
+            out
+        })
+    };
+    match super::prefix::Prefix::split_leaf(db_key) {
+        Some((prefix, key)) => {
+            let mut path = prefix
+                .iter()
+                .map(|byte| byte.to_string())
+                .collect::<Vec<_>>()
+                .join(".");
+            write!(path, "/{}", hex(key)).unwrap();
+            path
+        }
+        None => hex(db_key),
+    }
+}
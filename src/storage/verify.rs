@@ -0,0 +1,152 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    bigobject::{bigmap::Key, BigObject},
+    storage::{db::DbInner, lock_context::LockContext, prefix::Prefix},
+};
+
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub checked_keys: u64,
+    pub orphaned_keys: Vec<Vec<u8>>,
+    pub undecodable_keys: Vec<Vec<u8>>,
+    pub mismatched_keys: Vec<Vec<u8>>,
+    pub unrepairable_keys: Vec<Vec<u8>>,
+    pub stale_indexes: Vec<Vec<u8>>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.orphaned_keys.is_empty()
+            && self.undecodable_keys.is_empty()
+            && self.mismatched_keys.is_empty()
+            && self.stale_indexes.is_empty()
+    }
+}
+
+#[derive(Default)]
+pub struct Verifier {
+    live_prefixes: HashSet<Vec<u8>>,
+    skipped_prefixes: Vec<Vec<u8>>,
+    renamed_prefixes: Option<HashMap<Vec<u8>, Vec<u8>>>,
+    renamed_subtrees: Vec<(Vec<u8>, Vec<u8>)>,
+    report: VerifyReport,
+}
+
+impl Verifier {
+    pub(crate) fn run<T: BigObject>(root: &T, db: &Arc<DbInner>) -> VerifyReport {
+        let mut verifier = Verifier::default();
+        root.verify(&mut verifier);
+        verifier.scan(db)
+    }
+
+    /// Maps the prefix of every map to its prefix once map keys are resealed with the current
+    /// map key.
+    #[cfg(feature = "encryption")]
+    pub(crate) fn renamed_prefixes<T: BigObject>(root: &T) -> HashMap<Vec<u8>, Vec<u8>> {
+        let mut verifier = Verifier {
+            renamed_prefixes: Some(HashMap::new()),
+            ..Default::default()
+        };
+        root.verify(&mut verifier);
+        assert!(
+            verifier.report.undecodable_keys.is_empty(),
+            "Cannot reseal undecodable map keys"
+        );
+        verifier.renamed_prefixes.unwrap()
+    }
+
+    fn rename(&mut self, prefix: &[u8]) -> Option<Vec<u8>> {
+        let renamed_prefixes = self.renamed_prefixes.as_mut()?;
+        let renamed = match self.renamed_subtrees.last() {
+            Some((from, to)) => [to, &prefix[from.len()..]].concat(),
+            None => prefix.to_vec(),
+        };
+        renamed_prefixes.insert(prefix.to_vec(), renamed.clone());
+        Some(renamed)
+    }
+
+    pub(crate) fn visit_map<K: Key, V: BigObject>(&mut self, prefix: &Prefix) {
+        self.live_prefixes.insert(prefix.key.clone());
+        let renamed = self.rename(&prefix.key);
+        let db = prefix.db();
+        for (db_key, encoded) in LockContext::raw_iter(prefix, prefix.leaf_range()) {
+            let Some((key_prefix, key)) = Prefix::split_leaf(&db_key) else {
+                continue;
+            };
+            if key_prefix != prefix.key {
+                continue;
+            }
+            let value = db
+                .codec
+                .decode_map_key::<K>(key)
+                .and_then(|_| db.codec.unseal(&encoded))
+                .and_then(|(encoded, _)| rmp_serde::from_slice::<V>(&encoded).ok());
+            if let Some(mut value) = value {
+                let mut value_prefix = prefix.value_prefix(db_key.to_vec());
+                value.initialize(|| &mut value_prefix);
+                if let Some(renamed) = &renamed {
+                    let map_key = db.codec.reseal_map_key(key).unwrap();
+                    let subtree = [renamed, &[1][..], &map_key].concat();
+                    self.renamed_subtrees
+                        .push((value_prefix.key.clone(), subtree));
+                }
+                value.verify(self);
+                if renamed.is_some() {
+                    self.renamed_subtrees.pop();
+                }
+            } else {
+                let mut subtree = prefix.key.clone();
+                subtree.push(1);
+                subtree.extend_from_slice(key);
+                self.skipped_prefixes.push(subtree);
+                self.report.undecodable_keys.push(db_key.to_vec());
+            }
+        }
+    }
+
+    pub(crate) fn visit_index(&mut self, prefix: &Prefix) {
+        self.live_prefixes.insert(prefix.key.clone());
+        self.rename(&prefix.key);
+    }
+
+    pub(crate) fn report_stale_index(&mut self, prefix: &Prefix) {
+        self.report.stale_indexes.push(prefix.key.clone());
+    }
+
+    fn scan(mut self, db: &Arc<DbInner>) -> VerifyReport {
+        let undecodable: HashSet<_> = self.report.undecodable_keys.iter().cloned().collect();
+        for (db_key, _) in LockContext::raw_iter(&Prefix::root(db), (vec![], None)) {
+            if db_key.as_ref() == [0] {
+                continue;
+            }
+            self.report.checked_keys += 1;
+            if undecodable.contains(db_key.as_ref()) {
+                continue;
+            }
+            if let Some((prefix, _)) = Prefix::split_leaf(&db_key) {
+                if self.live_prefixes.contains(prefix) {
+                    continue;
+                }
+            }
+            if self
+                .skipped_prefixes
+                .iter()
+                .any(|prefix| db_key.starts_with(prefix))
+            {
+                continue;
+            }
+            if (0..db_key.len())
+                .any(|len| db_key[len] == 0 && self.live_prefixes.contains(&db_key[..len]))
+            {
+                self.report.mismatched_keys.push(db_key.to_vec());
+            } else {
+                self.report.orphaned_keys.push(db_key.to_vec());
+            }
+        }
+        self.report
+    }
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use bigobject::{BigMap, BigObject, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    int: i32,
+    dict: BigMap<String, i32>,
+}
+
+#[tokio::test]
+async fn async_guards() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<Data> = dir.open();
+        let mut write = db.write_async().await;
+        write.int = 1;
+        write.dict.insert("foo".to_string(), 2);
+        write.commit_async().await;
+        let read = db.read_async().await;
+        assert_eq!(1, read.int);
+        assert_eq!(Some(&2), read.dict.get_async("foo").await);
+    }
+    let db: Db<Data> = dir.open();
+    let read = db.read_async().await;
+    assert_eq!(Some(&2), read.dict.get_async("foo").await);
+    assert_eq!(None, read.dict.get_async("bar").await);
+    assert_eq!(2, db.stats().cache_misses);
+    Ok(())
+}
+
+#[tokio::test]
+async fn dropping_async_write_guard_discards_changes() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Db<Data> = dir.open();
+    db.write_async().await.int = 1;
+    assert_eq!(0, db.read_async().await.int);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn guards_across_await() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Arc<Db<Data>> = Arc::new(dir.open());
+    let writer = tokio::spawn({
+        let db = db.clone();
+        async move {
+            let mut write = db.write_async().await;
+            tokio::task::yield_now().await;
+            write.dict.insert("foo".to_string(), 1);
+            write.commit_async().await;
+        }
+    });
+    writer.await?;
+    let reader = tokio::spawn({
+        let db = db.clone();
+        async move {
+            let read = db.read_async().await;
+            let mut sum = 0;
+            for _ in 0..10 {
+                tokio::task::yield_now().await;
+                sum += read.dict.get_async("foo").await.unwrap();
+            }
+            sum
+        }
+    });
+    assert_eq!(10, reader.await?);
+    Ok(())
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{BigMap, BigObject, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    int: i32,
+    dict: BigMap<String, i32>,
+}
+
+fn fill(db: &Db<Data>) {
+    let mut write = db.w();
+    write.int = 7;
+    write.dict.insert("foo".to_string(), 1);
+    write.dict.insert("bar".to_string(), 2);
+}
+
+fn check(db: &Db<Data>) {
+    let read = db.r();
+    assert_eq!(7, read.int);
+    assert_eq!(Some(&1), read.dict.get("foo"));
+    assert_eq!(Some(&2), read.dict.get("bar"));
+    assert_eq!(None, read.dict.get("baz"));
+}
+
+#[test]
+fn checkpoint() -> Result<()> {
+    let dir = TestDir::new();
+    let checkpoint_dir = TestDir::new();
+    let checkpoint_path = checkpoint_dir.path().join("checkpoint");
+    {
+        let db: Db<Data> = dir.open();
+        fill(&db);
+        db.checkpoint(&checkpoint_path);
+        db.w().dict.insert("baz".to_string(), 3);
+    }
+    let db: Db<Data> = Db::open(&checkpoint_path);
+    check(&db);
+    Ok(())
+}
+
+#[test]
+fn backup_and_restore() -> Result<()> {
+    let dir = TestDir::new();
+    let backup_dir = TestDir::new();
+    let restore_dir = TestDir::new();
+    {
+        let db: Db<Data> = dir.open();
+        fill(&db);
+        db.backup_to(&backup_dir);
+        db.w().dict.insert("baz".to_string(), 3);
+    }
+    let db: Db<Data> = Db::restore_from(&backup_dir, &restore_dir);
+    check(&db);
+    Ok(())
+}
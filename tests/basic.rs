@@ -2,7 +2,7 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use tempfile::TempDir;
 
-use bigobject::{BigMap, Db};
+use bigobject::{BigMap, BigObject, Db};
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
 struct SerdeObj {
@@ -72,3 +72,121 @@ fn big_map_root() -> Result<()> {
     }
     Ok(())
 }
+
+#[test]
+fn replace_nested_map_drops_old_entries() -> Result<()> {
+    let dir = TempDir::new()?;
+    {
+        let db: Db<BigMap<u64, BigMap<u64, String>>> = Db::open(dir.path());
+        let mut inner = BigMap::default();
+        inner.insert(1, "old".to_string());
+        db.w().insert(1, inner);
+        let mut inner = BigMap::default();
+        inner.insert(2, "new".to_string());
+        db.w().insert(1, inner);
+    }
+    let db: Db<BigMap<u64, BigMap<u64, String>>> = Db::open(dir.path());
+    let read = db.r();
+    assert_eq!(None, read[&1].get(&1));
+    assert_eq!("new", read[&1][&2]);
+    Ok(())
+}
+
+#[test]
+fn replace_nested_map_overwrites_same_key() -> Result<()> {
+    let dir = TempDir::new()?;
+    {
+        let db: Db<BigMap<u64, BigMap<u64, String>>> = Db::open(dir.path());
+        let mut inner = BigMap::default();
+        inner.insert(1, "old".to_string());
+        db.w().insert(1, inner);
+        let mut inner = BigMap::default();
+        inner.insert(1, "new".to_string());
+        db.w().insert(1, inner);
+    }
+    let db: Db<BigMap<u64, BigMap<u64, String>>> = Db::open(dir.path());
+    assert_eq!("new", db.r()[&1][&1]);
+    Ok(())
+}
+
+#[test]
+fn replace_nested_map_keeps_siblings() -> Result<()> {
+    let dir = TempDir::new()?;
+    {
+        let db: Db<BigMap<u64, BigMap<u64, String>>> = Db::open(dir.path());
+        for key in 1..=2 {
+            let mut inner = BigMap::default();
+            inner.insert(1, format!("old-{key}"));
+            db.w().insert(key, inner);
+        }
+        let mut inner = BigMap::default();
+        inner.insert(2, "new".to_string());
+        db.w().insert(1, inner);
+    }
+    let db: Db<BigMap<u64, BigMap<u64, String>>> = Db::open(dir.path());
+    let read = db.r();
+    assert_eq!("new", read[&1][&2]);
+    assert_eq!("old-2", read[&2][&1]);
+    Ok(())
+}
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct MapThenField {
+    map: BigMap<u64, String>,
+    after: i32,
+}
+
+#[test]
+fn field_after_map_reopens() -> Result<()> {
+    let dir = TempDir::new()?;
+    {
+        let db: Db<MapThenField> = Db::open(dir.path());
+        let mut write = db.w();
+        write.map.insert(1, "one".to_string());
+        write.after = 7;
+    }
+    let db: Db<MapThenField> = Db::open(dir.path());
+    let read = db.r();
+    assert_eq!("one", read.map[&1]);
+    assert_eq!(7, read.after);
+    Ok(())
+}
+
+#[test]
+fn iterate_root_map() -> Result<()> {
+    let dir = TempDir::new()?;
+    let db: Db<BigMap<String, i32>> = Db::open(dir.path());
+    db.w().insert("a".to_string(), 1);
+    db.w().insert("b".to_string(), 2);
+    let read = db.r();
+    assert_eq!(
+        vec![("a".to_string(), &1), ("b".to_string(), &2)],
+        read.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(1, read.range("b".to_string()..).count());
+    Ok(())
+}
+
+#[test]
+fn nested_big_map_reopen() -> Result<()> {
+    let dir = TempDir::new()?;
+    for i in 0..3 {
+        let db: Db<BigMap<String, BigMap<u32, u32>>> = Db::open(dir.path());
+        let mut write = db.w();
+        let mut inner = BigMap::default();
+        inner.insert(i, i);
+        write.insert(format!("{i}"), inner);
+        assert_eq!(Some(&i), write[&format!("{i}")].get(&i));
+    }
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "while a guard is held")]
+fn reading_without_guard_panics() {
+    let dir = TempDir::new().unwrap();
+    let db: Db<BigMap<String, i32>> = Db::open(dir.path());
+    db.w().insert("abc".to_string(), 1);
+    let map = db.r().big_clone();
+    map.get("abc");
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{BigBitmap, BigObject, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    beta: BigBitmap,
+    paying: BigBitmap,
+}
+
+#[test]
+fn bitmap() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<Data> = dir.open();
+        let mut write = db.w();
+        for id in [1, 7, 40_000, 5_000_000] {
+            assert!(write.beta.set(id));
+        }
+        assert!(!write.beta.set(7));
+        for id in [7, 8, 5_000_000, 9_000_000] {
+            write.paying.set(id);
+        }
+    }
+    let db: Db<Data> = dir.open();
+    {
+        let read = db.r();
+        assert_eq!(read.beta.count(), 4);
+        assert!(read.beta.contains(40_000));
+        assert!(!read.beta.contains(40_001));
+        assert_eq!(
+            read.beta.iter().collect::<Vec<_>>(),
+            [1, 7, 40_000, 5_000_000]
+        );
+        assert_eq!(
+            read.beta.and(&read.paying).collect::<Vec<_>>(),
+            [7, 5_000_000]
+        );
+        assert_eq!(
+            read.beta.or(&read.paying).collect::<Vec<_>>(),
+            [1, 7, 8, 40_000, 5_000_000, 9_000_000]
+        );
+        assert_eq!(
+            read.beta.and_not(&read.paying).collect::<Vec<_>>(),
+            [1, 40_000]
+        );
+    }
+    {
+        let mut write = db.w();
+        assert!(write.beta.unset(40_000));
+        assert!(!write.beta.unset(40_000));
+        write.beta.unset(1);
+    }
+    let read = db.r();
+    assert_eq!(read.beta.count(), 2);
+    assert_eq!(read.beta.iter().collect::<Vec<_>>(), [7, 5_000_000]);
+    Ok(())
+}
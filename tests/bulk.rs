@@ -0,0 +1,114 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{BigMap, BigObject, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    rows: BigMap<u64, String>,
+    nested: BigMap<u64, BigMap<u64, String>>,
+}
+
+#[test]
+fn bulk_load() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<Data> = dir.open();
+        db.w().rows.insert(5, "old".to_string());
+        {
+            let mut write = db.w();
+            write
+                .rows
+                .bulk_load((0..10000).map(|i| (i, format!("row {i}"))));
+            write.rows.insert(10000, "last".to_string());
+        }
+        let read = db.r();
+        assert_eq!(Some(&"row 5".to_string()), read.rows.get(&5));
+        assert_eq!(Some(&"row 9999".to_string()), read.rows.get(&9999));
+        assert_eq!(10001, read.rows.iter().count());
+    }
+    assert_eq!(
+        0,
+        std::fs::read_dir(dir.path().join("bigobject-tmp"))?.count()
+    );
+    let db: Db<Data> = dir.open();
+    assert_eq!(Some(&"last".to_string()), db.r().rows.get(&10000));
+    assert!(db.verify().is_ok());
+    Ok(())
+}
+
+#[test]
+fn bulk_load_after_clear() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Db<Data> = dir.open();
+    db.w()
+        .rows
+        .bulk_load((0..10).map(|i| (i, format!("new {i}"))));
+    db.w().rows.insert(20, "stale".to_string());
+    {
+        let mut write = db.w();
+        write.rows.clear();
+        write
+            .rows
+            .bulk_load((0..10).map(|i| (i, format!("row {i}"))));
+    }
+    {
+        let mut write = db.w();
+        write
+            .rows
+            .bulk_load((10..20).map(|i| (i, format!("row {i}"))));
+        write.rows.insert(15, "updated".to_string());
+    }
+    let read = db.r();
+    assert_eq!(None, read.rows.get(&20));
+    assert_eq!(Some(&"row 3".to_string()), read.rows.get(&3));
+    assert_eq!(Some(&"updated".to_string()), read.rows.get(&15));
+    assert_eq!(20, read.rows.iter().count());
+    drop(read);
+    assert!(db.verify().is_ok());
+    Ok(())
+}
+
+#[test]
+fn bulk_load_into_moved_map() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Db<Data> = dir.open();
+    {
+        let mut write = db.w();
+        let mut inner = BigMap::default();
+        inner.insert(1, "one".to_string());
+        inner.insert(2, "two".to_string());
+        write.nested.insert(1, inner);
+    }
+    {
+        let mut write = db.w();
+        let inner = write.nested.get_mut(&1).unwrap();
+        inner.bulk_load([(1, "uno".to_string()), (3, "tres".to_string())]);
+        let moved = std::mem::take(inner);
+        write.nested.insert(2, moved);
+    }
+    let read = db.r();
+    assert_eq!(0, read.nested[&1].iter().count());
+    assert_eq!(
+        vec![
+            (1, "uno".to_string()),
+            (2, "two".to_string()),
+            (3, "tres".to_string())
+        ],
+        read.nested[&2]
+            .iter()
+            .map(|(key, value)| (key, value.clone()))
+            .collect::<Vec<_>>()
+    );
+    drop(read);
+    assert!(db.verify().is_ok());
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "belongs to a database")]
+fn bulk_load_into_detached_map() {
+    BigMap::<u64, String>::default().bulk_load([(1, "one".to_string())]);
+}
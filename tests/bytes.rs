@@ -0,0 +1,60 @@
+mod common;
+
+use std::io::{Read, Seek, SeekFrom};
+
+use anyhow::Result;
+use bigobject::{BigBytes, BigObject, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    file: BigBytes,
+}
+
+#[test]
+fn chunked_bytes() -> Result<()> {
+    let dir = TestDir::new();
+    let content: Vec<u8> = (0..300_000u32).map(|i| (i % 251) as u8).collect();
+    {
+        let db: Db<Data> = dir.open();
+        let mut write = db.w();
+        write.file.append(&content[..100_000]);
+        write.file.append(&content[100_000..]);
+        write.file.write_at(70_000, b"patched");
+    }
+    let mut expected = content.clone();
+    expected[70_000..70_007].copy_from_slice(b"patched");
+    let db: Db<Data> = dir.open();
+    {
+        let read = db.r();
+        assert_eq!(300_000, read.file.len());
+        let mut buf = vec![0; 20];
+        assert_eq!(20, read.file.read_at(69_995, &mut buf));
+        assert_eq!(&expected[69_995..70_015], &buf[..]);
+        let mut reader = read.file.reader();
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all)?;
+        assert_eq!(expected, all);
+        reader.seek(SeekFrom::End(-10))?;
+        let mut tail = Vec::new();
+        reader.read_to_end(&mut tail)?;
+        assert_eq!(&expected[299_990..], &tail[..]);
+        assert!(reader.seek(SeekFrom::Current(-400_000)).is_err());
+    }
+    {
+        let mut write = db.w();
+        write.file.truncate(100_000);
+        write.file.write_at(150_000, b"end");
+    }
+    let read = db.r();
+    assert_eq!(150_003, read.file.len());
+    let mut all = Vec::new();
+    read.file.reader().read_to_end(&mut all)?;
+    assert_eq!(&expected[..100_000], &all[..100_000]);
+    assert!(all[100_000..150_000].iter().all(|byte| *byte == 0));
+    assert_eq!(b"end", &all[150_000..]);
+    drop(read);
+    assert!(db.verify().is_ok());
+    Ok(())
+}
@@ -0,0 +1,45 @@
+#![allow(dead_code)]
+
+use std::{path::Path, process::Command};
+
+use bigobject::{BigObject, Db, DbOptions};
+use tempfile::TempDir;
+
+/// A temporary directory for a database that tests open, close and reopen.
+pub struct TestDir(TempDir);
+
+impl TestDir {
+    pub fn new() -> Self {
+        Self(tempfile::tempdir().unwrap())
+    }
+    pub fn path(&self) -> &Path {
+        self.0.path()
+    }
+    pub fn open<T: BigObject + Default>(&self) -> Db<T> {
+        Db::open(self.path())
+    }
+    pub fn open_with<T: BigObject + Default>(&self, options: DbOptions) -> Db<T> {
+        Db::open_with_options(self.path(), options)
+    }
+    /// The dump `bigobject-inspect` prints for a closed database, without any keys.
+    pub fn inspect(&self) -> String {
+        let output = Command::new(env!("CARGO_BIN_EXE_bigobject-inspect"))
+            .arg(self.path())
+            .env_remove("BIGOBJECT_KEY")
+            .env_remove("BIGOBJECT_MAP_KEY")
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    }
+}
+
+impl AsRef<Path> for TestDir {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{BigMap, BigObject, Compression, Db, DbOptions};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    blobs: BigMap<u64, String>,
+}
+
+#[test]
+fn compress_large_values() -> Result<()> {
+    let dir = TestDir::new();
+    let blob = "compressible ".repeat(100_000);
+    {
+        let options = DbOptions {
+            compression: Some(Compression {
+                min_size: 1024,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let db: Db<Data> = dir.open_with(options);
+        let mut write = db.w();
+        write.blobs.insert(1, blob.clone());
+        write.blobs.insert(2, "small".to_string());
+        drop(write);
+        assert!(db.stats().last_commit_bytes < 10_000);
+    }
+    let db: Db<Data> = dir.open();
+    assert_eq!(blob, db.r().blobs[&1]);
+    assert_eq!("small", db.r().blobs[&2]);
+    assert!(db.verify().is_ok());
+    Ok(())
+}
+
+#[test]
+fn compress_with_dictionary() -> Result<()> {
+    let dir = TestDir::new();
+    let record = |i: u64| {
+        format!(
+            r#"{{"id": {i}, "name": "user-{i}", "email": "user{i}@example.com", "status": "active"}}"#
+        )
+    };
+    let samples: Vec<_> = (0..1000).map(|i| record(i).into_bytes()).collect();
+    let options = DbOptions {
+        compression: Some(Compression {
+            min_size: 0,
+            dictionary: Some(zstd::dict::from_samples(&samples, 4096)?),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    {
+        let db: Db<Data> = dir.open_with(options.clone());
+        let mut write = db.w();
+        for i in 0..100 {
+            write.blobs.insert(i, record(i));
+        }
+        drop(write);
+        let raw_size: u64 = (0..100).map(|i| record(i).len() as u64).sum();
+        assert!(db.stats().last_commit_bytes < raw_size);
+    }
+    let db: Db<Data> = dir.open_with(options);
+    let read = db.r();
+    assert_eq!(record(42), read.blobs[&42]);
+    assert_eq!(100, read.blobs.iter().count());
+    Ok(())
+}
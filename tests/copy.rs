@@ -0,0 +1,92 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{BigCounterMap, BigMap, BigObject, BigVec, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Workspace {
+    name: String,
+    files: BigMap<String, BigMap<u64, String>>,
+    hits: BigCounterMap<String>,
+    history: BigVec<String>,
+}
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    workspaces: BigMap<String, Workspace>,
+    archive: BigMap<String, BigMap<u64, String>>,
+}
+
+fn lines(workspace: &Workspace, file: &str) -> Vec<(u64, String)> {
+    workspace.files[file]
+        .iter()
+        .map(|(line, text)| (line, text.clone()))
+        .collect()
+}
+
+#[test]
+fn deep_copy() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Db<Data> = dir.open();
+    {
+        let mut write = db.w();
+        let mut main = Workspace {
+            name: "main".to_string(),
+            ..Default::default()
+        };
+        for file in ["a", "b"] {
+            let mut lines = BigMap::default();
+            lines.insert(1, format!("{file} first"));
+            lines.insert(2, format!("{file} second"));
+            main.files.insert(file.to_string(), lines);
+        }
+        main.hits.add("a".to_string(), 3);
+        main.history.push("created".to_string());
+        write.workspaces.insert("main".to_string(), main);
+    }
+    {
+        let mut write = db.w();
+        let mut fork = Workspace::default();
+        fork.deep_copy_from(&write.workspaces["main"]);
+        fork.name = "fork".to_string();
+        fork.files
+            .get_mut("a")
+            .unwrap()
+            .insert(3, "fork third".to_string());
+        fork.files.remove("b");
+        fork.hits.add("a".to_string(), 1);
+        assert_eq!(lines(&fork, "a").len(), 3);
+        write.workspaces.insert("fork".to_string(), fork);
+        let mut archived = BigMap::default();
+        archived.deep_copy_from(&write.workspaces["main"].files["b"]);
+        write.archive.insert("main/b".to_string(), archived);
+    }
+    {
+        let mut write = db.w();
+        let main = write.workspaces.get_mut("main").unwrap();
+        main.files.get_mut("a").unwrap().remove(&1);
+        main.hits.add("a".to_string(), 10);
+    }
+    let read = db.r();
+    let (main, fork) = (&read.workspaces["main"], &read.workspaces["fork"]);
+    assert_eq!(fork.name, "fork");
+    assert_eq!(
+        lines(fork, "a"),
+        [
+            (1, "a first".to_string()),
+            (2, "a second".to_string()),
+            (3, "fork third".to_string())
+        ]
+    );
+    assert!(fork.files.get("b").is_none());
+    assert_eq!(lines(main, "a"), [(2, "a second".to_string())]);
+    assert_eq!(lines(main, "b").len(), 2);
+    assert_eq!((main.hits.get("a"), fork.hits.get("a")), (13, 4));
+    assert_eq!(fork.history.iter().collect::<Vec<_>>(), ["created"]);
+    assert_eq!(read.archive["main/b"].iter().count(), 2);
+    drop(read);
+    assert!(db.verify().is_ok());
+    Ok(())
+}
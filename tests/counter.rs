@@ -0,0 +1,50 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{BigCounterMap, BigObject, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    hits: BigCounterMap<String>,
+}
+
+#[test]
+fn counters() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<Data> = dir.open();
+        {
+            let mut write = db.w();
+            write.hits.add("a".to_string(), 2);
+            write.hits.add("a".to_string(), 3);
+            assert_eq!(5, write.hits.get("a"));
+        }
+        {
+            let mut write = db.w();
+            write.hits.add("a".to_string(), -1);
+            write.hits.add("b".to_string(), 7);
+        }
+        assert_eq!(4, db.r().hits.get("a"));
+        for _ in 0..10 {
+            db.w().hits.add("c".to_string(), 1);
+        }
+        let read = db.r();
+        assert_eq!(10, read.hits.get("c"));
+        assert_eq!(4, read.hits.get("a"));
+        assert_eq!(0, read.hits.get("z"));
+        assert_eq!(
+            vec![
+                ("a".to_string(), 4),
+                ("b".to_string(), 7),
+                ("c".to_string(), 10)
+            ],
+            read.hits.iter().collect::<Vec<_>>()
+        );
+    }
+    let db: Db<Data> = dir.open();
+    assert_eq!(10, db.r().hits.get("c"));
+    assert!(db.verify().is_ok());
+    Ok(())
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use std::time::Duration;
+
+use anyhow::Result;
+use bigobject::{BigMap, Db, DbOptions, Durability};
+use common::TestDir;
+
+#[test]
+fn durability_modes() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<BigMap<String, i32>> = dir.open_with(DbOptions {
+            durability: Durability::NoWal,
+            ..Default::default()
+        });
+        db.w().insert("no_wal".to_string(), 1);
+        {
+            let mut write = db.w();
+            write.set_durability(Durability::Sync);
+            write.insert("sync".to_string(), 2);
+        }
+        db.flush();
+        assert_eq!(
+            Some(0),
+            db.property_int_value("rocksdb.num-entries-active-mem-table")
+        );
+    }
+    {
+        let options = DbOptions {
+            durability: Durability::GroupCommit(Duration::from_millis(10)),
+            ..Default::default()
+        };
+        let db: Db<BigMap<String, i32>> = dir.open_with(options);
+        assert_eq!(Some(&1), db.r().get("no_wal"));
+        assert_eq!(Some(&2), db.r().get("sync"));
+        for i in 0..10 {
+            db.w().insert(format!("group{i}"), i);
+        }
+    }
+    let db: Db<BigMap<String, i32>> = dir.open();
+    assert_eq!(12, db.r().iter().count());
+    Ok(())
+}
@@ -0,0 +1,199 @@
+#![cfg(feature = "encryption")]
+
+mod common;
+
+use anyhow::Result;
+use bigobject::{
+    AnyMapIndex, BigCounterMap, BigHeap, BigIndexedMap, BigMap, BigObject, Db, DbOptions,
+    Encryption, MapIndex, MapIndexes,
+};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    secret: String,
+    map: BigMap<String, BigMap<u32, String>>,
+    counters: BigCounterMap<String>,
+    rows: BigMap<u64, String>,
+}
+
+fn options(key: u8, previous_keys: &[u8]) -> DbOptions {
+    DbOptions {
+        encryption: Some(Encryption {
+            key: [key; 32],
+            previous_keys: previous_keys.iter().map(|key| [*key; 32]).collect(),
+            map_key: Some([0xAA; 32]),
+            previous_map_key: None,
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn encrypted_values_and_keys() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<Data> = dir.open_with(options(1, &[]));
+        let mut write = db.w();
+        write.secret = "root-secret".to_string();
+        for i in 0..5 {
+            let mut inner = BigMap::default();
+            inner.insert(i, format!("value-secret-{i}"));
+            write.map.insert(format!("key-secret-{i}"), inner);
+        }
+        write.counters.add("counter-secret".to_string(), 3);
+        write.counters.add("large".to_string(), 0x5EC12E7);
+    }
+    let dump = dir.inspect();
+    assert!(dump.contains("<sealed>"));
+    assert!(!dump.contains("secret"));
+    assert!(!dump.contains(&0x5EC12E7.to_string()));
+    let db: Db<Data> = dir.open_with(options(1, &[]));
+    {
+        let mut write = db.w();
+        write.map.remove("key-secret-0");
+        write
+            .map
+            .insert("key-secret-9".to_string(), BigMap::default());
+        write.counters.add("counter-secret".to_string(), 1);
+        let mut keys: Vec<_> = write.map.iter().map(|(key, _)| key).collect();
+        keys.sort();
+        assert_eq!(
+            vec![
+                "key-secret-1",
+                "key-secret-2",
+                "key-secret-3",
+                "key-secret-4",
+                "key-secret-9"
+            ],
+            keys
+        );
+        assert_eq!(
+            3,
+            write
+                .map
+                .range("key-secret-2".to_string()..="key-secret-4".to_string())
+                .count()
+        );
+    }
+    let read = db.r();
+    assert_eq!("root-secret", read.secret);
+    assert_eq!(
+        Some("value-secret-3"),
+        read.map["key-secret-3"].get(&3).map(String::as_str)
+    );
+    let mut counters: Vec<_> = read.counters.iter().collect();
+    counters.sort();
+    assert_eq!(
+        vec![
+            ("counter-secret".to_string(), 4),
+            ("large".to_string(), 0x5EC12E7)
+        ],
+        counters
+    );
+    drop(read);
+    assert!(db.verify().is_ok());
+    Ok(())
+}
+
+#[test]
+fn key_rotation() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<Data> = dir.open_with(options(1, &[]));
+        let mut write = db.w();
+        write.secret = "root".to_string();
+        let mut inner = BigMap::default();
+        inner.insert(1, "one".to_string());
+        write.map.insert("a".to_string(), inner);
+    }
+    {
+        let db: Db<Data> = dir.open_with(options(2, &[1]));
+        assert_eq!("one", db.r().map["a"][&1]);
+        assert_eq!(3, db.reencrypt());
+        assert_eq!(0, db.reencrypt());
+    }
+    let db: Db<Data> = dir.open_with(options(2, &[]));
+    assert_eq!("root", db.r().secret);
+    assert_eq!("one", db.r().map["a"][&1]);
+    Ok(())
+}
+
+#[test]
+fn map_key_rotation() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<Data> = dir.open_with(options(1, &[]));
+        let mut write = db.w();
+        let mut inner = BigMap::default();
+        inner.insert(1, "one".to_string());
+        write.map.insert("a".to_string(), inner);
+        write.counters.add("b".to_string(), 2);
+        write.counters.add("b".to_string(), 3);
+    }
+    let mut rotated = options(2, &[1]);
+    let encryption = rotated.encryption.as_mut().unwrap();
+    encryption.map_key = Some([0xBB; 32]);
+    encryption.previous_map_key = Some([0xAA; 32]);
+    {
+        let db: Db<Data> = dir.open_with(rotated.clone());
+        assert_eq!(4, db.reencrypt());
+        assert_eq!(0, db.reencrypt());
+        assert!(db.verify().is_ok());
+    }
+    rotated.encryption.as_mut().unwrap().previous_map_key = None;
+    rotated.encryption.as_mut().unwrap().previous_keys.clear();
+    let db: Db<Data> = dir.open_with(rotated);
+    assert_eq!("one", db.r().map["a"][&1]);
+    assert_eq!(5, db.r().counters.get("b"));
+    assert!(db.verify().is_ok());
+    Ok(())
+}
+
+#[test]
+fn bulk_load_with_encrypted_keys() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Db<Data> = dir.open_with(options(1, &[]));
+    db.w()
+        .rows
+        .bulk_load((0..100).map(|i| (i, format!("row-secret-{i}"))));
+    assert_eq!(100, db.r().rows.iter().count());
+    assert_eq!("row-secret-42", db.r().rows[&42]);
+    assert!(db.verify().is_ok());
+    drop(db);
+    assert!(!dir.inspect().contains("secret"));
+    Ok(())
+}
+
+const BY_LEN: MapIndex<String, usize> = MapIndex::new("len", String::len);
+
+struct LenIndex;
+
+impl MapIndexes<String> for LenIndex {
+    const INDEXES: &'static [&'static dyn AnyMapIndex<String>] = &[&BY_LEN];
+}
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Indexed {
+    names: BigIndexedMap<u64, String, LenIndex>,
+}
+
+#[test]
+#[should_panic(expected = "cannot use map-key encryption")]
+fn indexed_map_rejects_encrypted_keys() {
+    let dir = TestDir::new();
+    Db::<Indexed>::open_with_options(&dir, options(1, &[]));
+}
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Queue {
+    jobs: BigHeap<u64, String>,
+}
+
+#[test]
+#[should_panic(expected = "cannot use map-key encryption")]
+fn ordered_containers_reject_encrypted_keys() {
+    let dir = TestDir::new();
+    Db::<Queue>::open_with_options(&dir, options(1, &[]));
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{BigCounterMap, BigMap, BigObject, BigVec, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Inner {
+    name: String,
+    items: BigMap<u64, String>,
+    counts: BigCounterMap<u64>,
+    list: BigVec<BigMap<u64, u64>>,
+}
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    inners: BigMap<u64, Inner>,
+    single: Inner,
+}
+
+fn inner(n: u64) -> Inner {
+    let mut inner = Inner {
+        name: format!("inner {n}"),
+        ..Default::default()
+    };
+    for i in 0..n {
+        inner.items.insert(i, i.to_string());
+        inner.counts.add(i, 1);
+        let mut map = BigMap::default();
+        map.insert(i, i);
+        inner.list.push(map);
+    }
+    inner
+}
+
+fn checked_keys(db: &Db<Data>) -> u64 {
+    let report = db.verify();
+    assert!(report.is_ok(), "{report:?}");
+    report.checked_keys
+}
+
+#[test]
+fn overwrites_leave_no_orphans() -> Result<()> {
+    let dir = TestDir::new();
+    let overwritten: Db<Data> = dir.open();
+    {
+        let mut write = overwritten.w();
+        write.inners.insert(1, inner(50));
+        write.inners.insert(2, inner(50));
+        write.single = inner(50);
+    }
+    {
+        let mut write = overwritten.w();
+        write.inners.insert(1, inner(5));
+        write.inners.remove(&2);
+        write.inners.insert(2, inner(3));
+        write.single = inner(4);
+    }
+    {
+        let mut write = overwritten.w();
+        write.inners[&1].items.clear();
+        write.single.list.truncate(1);
+    }
+    let dir = TestDir::new();
+    let direct: Db<Data> = dir.open();
+    {
+        let mut write = direct.w();
+        let mut first = inner(5);
+        first.items.clear();
+        write.inners.insert(1, first);
+        write.inners.insert(2, inner(3));
+        write.single = inner(4);
+        write.single.list.truncate(1);
+    }
+    assert_eq!(overwritten.r().inners[&2].items.iter().count(), 3);
+    assert_eq!(checked_keys(&overwritten), checked_keys(&direct));
+    Ok(())
+}
+
+#[test]
+fn moving_nested_container_copies() -> Result<()> {
+    let dir = TestDir::new();
+    let moved: Db<Data> = dir.open();
+    moved.w().inners.insert(1, inner(3));
+    {
+        let mut write = moved.w();
+        let inner = std::mem::take(&mut write.inners[&1]);
+        write.inners.insert(2, inner);
+    }
+    {
+        let read = moved.r();
+        assert_eq!(read.inners[&1].items.iter().count(), 0);
+        assert_eq!(read.inners[&2].items.get(&2), Some(&"2".to_string()));
+        assert_eq!(read.inners[&2].counts.get(&1), 1);
+        assert_eq!(read.inners[&2].list[2].get(&2), Some(&2));
+    }
+    let dir = TestDir::new();
+    let direct: Db<Data> = dir.open();
+    {
+        let mut write = direct.w();
+        write.inners.insert(1, Inner::default());
+        write.inners.insert(2, inner(3));
+    }
+    assert_eq!(checked_keys(&moved), checked_keys(&direct));
+    Ok(())
+}
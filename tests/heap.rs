@@ -0,0 +1,47 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{BigHeap, BigObject, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    jobs: BigHeap<u64, String>,
+}
+
+#[test]
+fn heap() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<Data> = dir.open();
+        let mut write = db.w();
+        write.jobs.push(30, "c".to_string());
+        write.jobs.push(10, "a".to_string());
+        write.jobs.push(20, "b1".to_string());
+        write.jobs.push(20, "b2".to_string());
+        assert_eq!(write.jobs.peek(), Some((10, &"a".to_string())));
+    }
+    let db: Db<Data> = dir.open();
+    assert_eq!(db.r().jobs.len(), 4);
+    {
+        let mut write = db.w();
+        assert_eq!(write.jobs.pop(), Some((10, "a".to_string())));
+        assert_eq!(write.jobs.pop(), Some((20, "b1".to_string())));
+        write.jobs.push(5, "first".to_string());
+        assert_eq!(write.jobs.peek(), Some((5, &"first".to_string())));
+    }
+    let mut write = db.w();
+    let order: Vec<_> = std::iter::from_fn(|| write.jobs.pop()).collect();
+    assert_eq!(
+        order,
+        [
+            (5, "first".to_string()),
+            (20, "b2".to_string()),
+            (30, "c".to_string())
+        ]
+    );
+    assert!(write.jobs.is_empty());
+    assert_eq!(write.jobs.pop(), None);
+    Ok(())
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{AnyMapIndex, BigIndexedMap, BigMap, BigObject, Db, MapIndex, MapIndexes};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+struct User {
+    email: String,
+    age: u32,
+}
+
+const BY_EMAIL: MapIndex<User, String> = MapIndex::new("email", |user| user.email.clone());
+const BY_AGE: MapIndex<User, u32> = MapIndex::new("age", |user| user.age);
+
+struct UserIndexes;
+
+impl MapIndexes<User> for UserIndexes {
+    const INDEXES: &'static [&'static dyn AnyMapIndex<User>] = &[&BY_EMAIL, &BY_AGE];
+}
+
+type Users = BigIndexedMap<u64, User, UserIndexes>;
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    users: Users,
+    moved: BigMap<u64, Users>,
+}
+
+fn user(email: &str, age: u32) -> User {
+    User {
+        email: email.to_string(),
+        age,
+    }
+}
+
+#[test]
+fn secondary_index() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Db<Data> = dir.open();
+    {
+        let mut write = db.w();
+        write.users.insert(1, user("a@example.com", 30));
+        write.users.insert(2, user("b@example.com", 40));
+        write.users.insert(3, user("c@example.com", 50));
+    }
+    {
+        let mut write = db.w();
+        write.users.insert(2, user("d@example.com", 41));
+        write.users.remove(&3);
+        write
+            .users
+            .update(&1, |user| user.email = "e@example.com".to_string());
+    }
+    let read = db.r();
+    let by_email = |email: &str| -> Vec<u64> {
+        read.users
+            .get_by_index(&BY_EMAIL, &email.to_string())
+            .map(|(id, _)| id)
+            .collect()
+    };
+    assert_eq!(Vec::<u64>::new(), by_email("a@example.com"));
+    assert_eq!(Vec::<u64>::new(), by_email("b@example.com"));
+    assert_eq!(Vec::<u64>::new(), by_email("c@example.com"));
+    assert_eq!(vec![2], by_email("d@example.com"));
+    assert_eq!(vec![1], by_email("e@example.com"));
+    assert_eq!(
+        vec![(1, 30)],
+        read.users
+            .range_by_index(&BY_EMAIL, "d@example.com".to_string()..)
+            .skip(1)
+            .map(|(id, user)| (id, user.age))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![1, 2],
+        read.users
+            .range_by_index(&BY_AGE, 30..)
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>()
+    );
+    drop(read);
+    assert!(db.verify().is_ok());
+    Ok(())
+}
+
+#[test]
+fn uncommitted_and_moved_entries() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Db<Data> = dir.open();
+    {
+        let mut write = db.w();
+        write.users.insert(1, user("a@example.com", 30));
+        write.users.insert(2, user("b@example.com", 40));
+    }
+    {
+        let mut write = db.w();
+        write.users.insert(3, user("c@example.com", 35));
+        write.users.update(&2, |user| user.age = 20);
+        let ages = |users: &Users| -> Vec<(u32, u64)> {
+            users
+                .range_by_index(&BY_AGE, ..)
+                .map(|(id, user)| (user.age, id))
+                .collect()
+        };
+        assert_eq!(vec![(20, 2), (30, 1), (35, 3)], ages(&write.users));
+        let users = std::mem::take(&mut write.users);
+        write.moved.insert(7, users);
+        write.moved.get_mut(&7).unwrap().remove(&1);
+        assert_eq!(vec![(20, 2), (35, 3)], ages(write.moved.get(&7).unwrap()));
+    }
+    let read = db.r();
+    assert_eq!(0, read.users.range_by_index(&BY_AGE, ..).count());
+    let moved = read.moved.get(&7).unwrap();
+    assert_eq!(
+        vec![3],
+        moved
+            .get_by_index(&BY_EMAIL, &"c@example.com".to_string())
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        0,
+        moved
+            .get_by_index(&BY_EMAIL, &"a@example.com".to_string())
+            .count()
+    );
+    drop(read);
+    assert!(db.verify().is_ok());
+    Ok(())
+}
+
+struct EmailIndex;
+
+impl MapIndexes<User> for EmailIndex {
+    const INDEXES: &'static [&'static dyn AnyMapIndex<User>] = &[&BY_EMAIL];
+}
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct EmailOnly {
+    users: BigIndexedMap<u64, User, EmailIndex>,
+    moved: BigMap<u64, Users>,
+}
+
+#[test]
+fn stale_index_is_reported() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<EmailOnly> = dir.open();
+        let mut write = db.w();
+        write.users.insert(1, user("a@example.com", 30));
+        write.users.insert(2, user("b@example.com", 40));
+    }
+    let db: Db<Data> = dir.open();
+    let report = db.verify();
+    assert_eq!(1, report.stale_indexes.len());
+    assert!(!report.is_ok());
+    Ok(())
+}
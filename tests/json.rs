@@ -0,0 +1,140 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{
+    AnyMapIndex, BigBytes, BigCounterMap, BigIndexedMap, BigMap, BigObject, BigVec, Db, MapIndex,
+    MapIndexes,
+};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    int: i32,
+    dict: BigMap<String, Inner>,
+    list: BigVec<String>,
+}
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Inner {
+    name: String,
+    scores: BigMap<u32, u64>,
+}
+
+#[test]
+fn export_import() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Db<Data> = dir.open();
+    {
+        let mut write = db.w();
+        write.int = 4;
+        let mut inner = Inner {
+            name: "foo".to_string(),
+            ..Default::default()
+        };
+        inner.scores.insert(1, 10);
+        inner.scores.insert(2, 20);
+        write.dict.insert("foo".to_string(), inner);
+        write.list.push("a".to_string());
+        write.list.push("b".to_string());
+    }
+    db.w().dict["foo"].scores.insert(3, 30);
+    let mut exported = Vec::new();
+    db.export_json(&mut exported)?;
+    let value: serde_json::Value = serde_json::from_slice(&exported)?;
+    assert_eq!(
+        serde_json::json!({
+            "int": 4,
+            "dict": [["foo", {"name": "foo", "scores": [[1, 10], [2, 20], [3, 30]]}]],
+            "list": ["a", "b"],
+        }),
+        value
+    );
+
+    let other_dir = TestDir::new();
+    let other: Db<Data> = other_dir.open();
+    other.w().dict.insert("stale".to_string(), Inner::default());
+    other.import_json(exported.as_slice())?;
+    let read = other.r();
+    assert_eq!(4, read.int);
+    assert!(read.dict.get("stale").is_none());
+    assert_eq!("foo", read.dict["foo"].name);
+    assert_eq!(Some(&30), read.dict["foo"].scores.get(&3));
+    assert_eq!(
+        vec!["a", "b"],
+        read.list.iter().map(String::as_str).collect::<Vec<_>>()
+    );
+    Ok(())
+}
+
+#[test]
+fn export_after_reopen() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<Data> = dir.open();
+        let mut write = db.w();
+        let mut inner = Inner::default();
+        inner.scores.insert(1, 10);
+        write.dict.insert("foo".to_string(), inner);
+    }
+    let db: Db<Data> = dir.open();
+    let mut exported = Vec::new();
+    db.export_json(&mut exported)?;
+    let value: serde_json::Value = serde_json::from_slice(&exported)?;
+    assert_eq!(
+        serde_json::json!([["foo", {"name": "", "scores": [[1, 10]]}]]),
+        value["dict"]
+    );
+    Ok(())
+}
+
+const BY_LEN: MapIndex<String, usize> = MapIndex::new("len", String::len);
+
+struct ByLen;
+
+impl MapIndexes<String> for ByLen {
+    const INDEXES: &'static [&'static dyn AnyMapIndex<String>] = &[&BY_LEN];
+}
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Large {
+    nested: BigMap<u32, BigVec<u32>>,
+    words: BigIndexedMap<u32, String, ByLen>,
+    counts: BigCounterMap<u32>,
+    bytes: BigBytes,
+}
+
+#[test]
+fn import_large_document() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Db<Large> = dir.open();
+    {
+        let mut write = db.w_spilling();
+        for key in 0..1500 {
+            let mut vec = BigVec::default();
+            vec.push(key);
+            write.nested.insert(key, vec);
+            write.words.insert(key, "x".repeat(key as usize % 5));
+            write.counts.add(key, key as i64);
+        }
+        write.bytes.append(&vec![7; 100_000]);
+    }
+    let mut exported = Vec::new();
+    db.export_json(&mut exported)?;
+
+    let other_dir = TestDir::new();
+    let other: Db<Large> = other_dir.open();
+    other.import_json(exported.as_slice())?;
+    let read = other.r();
+    assert_eq!(1500, read.nested.iter().count());
+    assert_eq!(Some(&1499), read.nested[&1499].iter().next());
+    assert_eq!(300, read.words.get_by_index(&BY_LEN, &3).count());
+    assert_eq!(1499, read.counts.get(&1499));
+    assert_eq!(100_000, read.bytes.len());
+    drop(read);
+    assert!(other.verify().is_ok());
+    let mut reexported = Vec::new();
+    other.export_json(&mut reexported)?;
+    assert_eq!(exported, reexported);
+    Ok(())
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{BigLog, BigObject, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    events: BigLog<String>,
+}
+
+#[test]
+fn log() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<Data> = dir.open();
+        let mut write = db.w();
+        for i in 0..10_000 {
+            assert_eq!(write.events.append(format!("event {i}")), i);
+        }
+        write.events.set_consumer_offset("indexer", 42);
+    }
+    let db: Db<Data> = dir.open();
+    {
+        let read = db.r();
+        assert_eq!(read.events.len(), 10_000);
+        assert_eq!(read.events.get(9_999).unwrap(), "event 9999");
+        let offset = read.events.consumer_offset("indexer").unwrap();
+        let batch: Vec<_> = read.events.read_from(offset).take(2).collect();
+        assert_eq!(
+            batch,
+            [(42, &"event 42".to_string()), (43, &"event 43".to_string())]
+        );
+        assert_eq!(read.events.consumer_offset("mailer"), None);
+    }
+    assert_eq!(db.w().events.truncate_before(5_000), 5_000);
+    {
+        let read = db.r();
+        assert_eq!(read.events.start(), 5_000);
+        assert_eq!(read.events.get(4_999), None);
+        assert_eq!(read.events.read_from(0).next().unwrap().0, 5_000);
+    }
+    {
+        let mut write = db.w();
+        assert_eq!(write.events.append("tail".to_string()), 10_000);
+        assert_eq!(write.events.truncate_before(20_000), 5_001);
+        assert!(write.events.is_empty());
+        assert_eq!(write.events.append("next".to_string()), 10_001);
+    }
+    let read = db.r();
+    assert_eq!(read.events.read_from(0).count(), 1);
+    assert_eq!(
+        read.events.consumers().collect::<Vec<_>>(),
+        [("indexer".to_string(), 42)]
+    );
+    Ok(())
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{BigMultiMap, BigObject, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    followers: BigMultiMap<String, u64>,
+}
+
+#[test]
+fn multimap() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<Data> = dir.open();
+        let mut write = db.w();
+        assert!(write.followers.insert("alice".to_string(), 3));
+        assert!(write.followers.insert("alice".to_string(), 1));
+        assert!(!write.followers.insert("alice".to_string(), 3));
+        write.followers.insert("bob".to_string(), 2);
+    }
+    let db: Db<Data> = dir.open();
+    {
+        let read = db.r();
+        assert_eq!(read.followers.get_all("alice").collect::<Vec<_>>(), [1, 3]);
+        assert_eq!(read.followers.count("alice"), 2);
+        assert!(read.followers.contains("bob", &2));
+        assert_eq!(read.followers.count("carol"), 0);
+    }
+    {
+        let mut write = db.w();
+        write.followers.insert("alice".to_string(), 2);
+        assert!(write.followers.remove("alice", &3));
+        assert!(!write.followers.remove("alice", &3));
+        assert!(write.followers.remove("bob", &2));
+        assert_eq!(write.followers.get_all("alice").collect::<Vec<_>>(), [1, 2]);
+    }
+    {
+        let read = db.r();
+        assert_eq!(read.followers.count("alice"), 2);
+        assert_eq!(read.followers.keys().collect::<Vec<_>>(), ["alice"]);
+    }
+    db.w().followers.remove_all("alice");
+    let read = db.r();
+    assert_eq!(read.followers.count("alice"), 0);
+    assert_eq!(read.followers.get_all("alice").count(), 0);
+    Ok(())
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{BigMap, BigObject, Db, ReadOnlyDb};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    int: i32,
+    dict: BigMap<String, i32>,
+}
+
+#[test]
+fn read_only() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<Data> = dir.open();
+        let mut write = db.w();
+        write.int = 1;
+        write.dict.insert("foo".to_string(), 2);
+    }
+    let db: ReadOnlyDb<Data> = Db::open_read_only(&dir);
+    assert_eq!(1, db.r().int);
+    assert_eq!(Some(&2), db.r().dict.get("foo"));
+    Ok(())
+}
+
+#[test]
+fn secondary() -> Result<()> {
+    let dir = TestDir::new();
+    let secondary_dir = TestDir::new();
+    let primary: Db<Data> = dir.open();
+    primary.w().dict.insert("foo".to_string(), 1);
+    let secondary: ReadOnlyDb<Data> = Db::open_as_secondary(&dir, &secondary_dir);
+    assert_eq!(Some(&1), secondary.r().dict.get("foo"));
+    {
+        let mut write = primary.w();
+        write.int = 3;
+        write.dict.insert("foo".to_string(), 2);
+    }
+    assert_eq!(0, secondary.r().int);
+    assert_eq!(Some(&1), secondary.r().dict.get("foo"));
+    secondary.try_catch_up_with_primary();
+    assert_eq!(3, secondary.r().int);
+    assert_eq!(Some(&2), secondary.r().dict.get("foo"));
+    Ok(())
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{BigCounterMap, BigMap, BigObject, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    rows: BigMap<u64, String>,
+    counters: BigCounterMap<u64>,
+    nested: BigMap<u64, BigMap<u64, String>>,
+}
+
+fn staging_dirs(dir: &TestDir) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir.path().join("bigobject-tmp")) else {
+        return 0;
+    };
+    entries
+        .filter(|entry| {
+            entry
+                .as_ref()
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with("staging-")
+        })
+        .count()
+}
+
+#[test]
+fn spilling_transaction() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Db<Data> = dir.open();
+    {
+        let mut write = db.w();
+        write.rows.insert(0, "zero".to_string());
+        write.rows.insert(1, "one".to_string());
+        write.counters.add(0, 5);
+    }
+    {
+        let mut write = db.w_spilling();
+        assert_eq!(1, staging_dirs(&dir));
+        for i in 2..5000 {
+            write.rows.insert(i, i.to_string());
+            write.counters.add(i % 3, 1);
+        }
+        write.rows.remove(&0);
+        write.rows.insert(1, "uno".to_string());
+        assert_eq!(None, write.rows.get(&0));
+        assert_eq!(Some(&"uno".to_string()), write.rows.get(&1));
+        assert_eq!(Some(&"2".to_string()), write.rows.get(&2));
+        assert_eq!(4999, write.rows.iter().count());
+        assert_eq!(5 + 1666, write.counters.get(&0));
+        assert_eq!(None, db.r().rows.get(&2));
+    }
+    assert_eq!(0, staging_dirs(&dir));
+    let read = db.r();
+    assert_eq!(None, read.rows.get(&0));
+    assert_eq!(Some(&"uno".to_string()), read.rows.get(&1));
+    assert_eq!(Some(&"4999".to_string()), read.rows.get(&4999));
+    assert_eq!(4999, read.rows.iter().count());
+    assert_eq!(
+        vec![(0, 5 + 1666), (1, 1666), (2, 1666)],
+        read.counters.iter().collect::<Vec<_>>()
+    );
+    drop(read);
+    {
+        let mut write = db.w_spilling();
+        write.rows.clear();
+        write.rows.insert(7, "seven".to_string());
+        assert_eq!(1, write.rows.iter().count());
+    }
+    assert_eq!(1, db.r().rows.iter().count());
+    assert!(db.verify().is_ok());
+    Ok(())
+}
+
+#[test]
+fn spilling_new_and_cleared_maps() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Db<Data> = dir.open();
+    db.w().rows.insert(0, "zero".to_string());
+    {
+        let mut write = db.w_spilling();
+        write.rows.clear();
+        write.counters.clear();
+        write.nested.insert(1, BigMap::default());
+        for i in 1..3000 {
+            write.rows.insert(i, i.to_string());
+            write.counters.add(i, 1);
+            write.nested.get_mut(&1).unwrap().insert(i, i.to_string());
+        }
+        assert_eq!(None, write.rows.get(&0));
+        assert_eq!(2999, write.rows.iter().count());
+        assert_eq!(2999, write.counters.iter().count());
+        assert_eq!(2999, write.nested.get(&1).unwrap().iter().count());
+    }
+    let read = db.r();
+    assert_eq!(None, read.rows.get(&0));
+    assert_eq!(Some(&"2999".to_string()), read.rows.get(&2999));
+    assert_eq!(2999, read.counters.iter().count());
+    assert_eq!(
+        Some(&"1500".to_string()),
+        read.nested.get(&1).unwrap().get(&1500)
+    );
+    drop(read);
+    assert!(db.verify().is_ok());
+    Ok(())
+}
+
+#[test]
+fn spilling_clear_deletes_ranges() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Db<Data> = dir.open();
+    {
+        let mut write = db.w();
+        let mut inner = BigMap::default();
+        for i in 0..5000 {
+            write.rows.insert(i, i.to_string());
+            write.counters.add(i, 1);
+            inner.insert(i, i.to_string());
+        }
+        write.nested.insert(1, inner);
+    }
+    {
+        let mut write = db.w_spilling();
+        write.rows.clear();
+        write.rows.insert(7, "seven".to_string());
+        write.counters.clear();
+        write.counters.add(3, 2);
+        write.nested.remove(&1);
+        assert_eq!(1, write.rows.iter().count());
+        assert_eq!(2, write.counters.get(&3));
+        assert!(write.nested.get(&1).is_none());
+    }
+    assert!(db.stats().last_commit_ops < 10);
+    let read = db.r();
+    assert_eq!(
+        vec![(7, &"seven".to_string())],
+        read.rows.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(vec![(3, 2)], read.counters.iter().collect::<Vec<_>>());
+    assert_eq!(0, read.nested.iter().count());
+    drop(read);
+    assert!(db.verify().is_ok());
+    Ok(())
+}
+
+#[test]
+fn leftovers_removed_on_open() -> Result<()> {
+    let dir = TestDir::new();
+    drop(Db::<Data>::open(&dir));
+    let leftover = dir.path().join("bigobject-tmp").join("staging-0-0");
+    std::fs::create_dir_all(&leftover)?;
+    std::fs::write(leftover.join("CURRENT"), "")?;
+    std::fs::write(dir.path().join("bigobject-tmp").join("bulk-0-1.sst"), "")?;
+    let db: Db<Data> = dir.open();
+    assert!(!dir.path().join("bigobject-tmp").exists());
+    db.w().rows.insert(1, "one".to_string());
+    assert!(db.verify().is_ok());
+    Ok(())
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{BigMap, Db};
+use common::TestDir;
+
+#[test]
+fn cache_and_commit_stats() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<BigMap<String, i32>> = dir.open();
+        db.w().insert("foo".to_string(), 1);
+        let stats = db.stats();
+        assert_eq!(1, stats.commits);
+        assert_eq!(2, stats.last_commit_ops);
+        assert!(stats.last_commit_bytes > 0);
+        assert_eq!(1, stats.cache_entries);
+    }
+    let db: Db<BigMap<String, i32>> = dir.open();
+    assert_eq!(Some(&1), db.r().get("foo"));
+    assert_eq!(Some(&1), db.r().get("foo"));
+    assert_eq!(None, db.r().get("bar"));
+    let stats = db.stats();
+    assert_eq!(1, stats.cache_hits);
+    assert_eq!(2, stats.cache_misses);
+    assert_eq!(0, stats.commits);
+    assert_eq!(2, stats.cache_entries);
+    assert!(stats.estimate_num_keys.is_some());
+    assert!(db.property_int_value("rocksdb.block-cache-usage").is_some());
+    Ok(())
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use std::ops::Bound;
+
+use anyhow::Result;
+use bigobject::{BigObject, BigTimeSeries, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(BigObject, Serialize, Deserialize)]
+struct Data {
+    latency: BigTimeSeries<u32>,
+}
+
+impl Default for Data {
+    fn default() -> Self {
+        Self {
+            latency: BigTimeSeries::new(10),
+        }
+    }
+}
+
+#[test]
+fn time_series() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<Data> = dir.open();
+        let mut write = db.w();
+        for (timestamp, value) in [(3, 30), (5, 50), (5, 51), (12, 120), (27, 270), (31, 310)] {
+            write.latency.append(timestamp, value);
+        }
+    }
+    let db: Db<Data> = dir.open();
+    {
+        let read = db.r();
+        assert_eq!(read.latency.len(), 6);
+        assert_eq!(
+            read.latency.range(5..=27).collect::<Vec<_>>(),
+            [(5, &50), (5, &51), (12, &120), (27, &270)]
+        );
+        assert_eq!(read.latency.range(..5).count(), 1);
+        assert_eq!(
+            read.latency
+                .range((Bound::Excluded(12), Bound::Unbounded))
+                .collect::<Vec<_>>(),
+            [(27, &270), (31, &310)]
+        );
+        assert_eq!(
+            read.latency
+                .range((Bound::Excluded(3), Bound::Included(12)))
+                .collect::<Vec<_>>(),
+            [(5, &50), (5, &51), (12, &120)]
+        );
+        let buckets: Vec<_> = read
+            .latency
+            .downsample(.., 15)
+            .map(|(start, values)| (start, values.into_iter().sum::<u32>()))
+            .collect();
+        assert_eq!(buckets, [(0, 251), (15, 270), (30, 310)]);
+    }
+    assert_eq!(db.w().latency.drop_before(12), 3);
+    {
+        let read = db.r();
+        assert_eq!(read.latency.len(), 3);
+        assert_eq!(
+            read.latency.range(..).collect::<Vec<_>>(),
+            [(12, &120), (27, &270), (31, &310)]
+        );
+    }
+    assert_eq!(db.w().latency.drop_before(30), 2);
+    assert_eq!(db.r().latency.range(..).collect::<Vec<_>>(), [(31, &310)]);
+    Ok(())
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::Result;
+use bigobject::{BigObject, BigTtlMap, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Data {
+    sessions: BigTtlMap<String, u64>,
+}
+
+#[test]
+fn ttl_map() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Db<Data> = dir.open();
+    {
+        let mut write = db.w();
+        let past = SystemTime::now() - Duration::from_secs(1);
+        write.sessions.insert_until("expired".to_string(), 1, past);
+        write
+            .sessions
+            .insert("live".to_string(), 2, Duration::from_secs(3600));
+        write
+            .sessions
+            .insert("renewed".to_string(), 3, Duration::from_secs(3600));
+    }
+    {
+        let mut write = db.w();
+        let past = SystemTime::now() - Duration::from_secs(1);
+        write.sessions.insert_until("renewed".to_string(), 4, past);
+        write
+            .sessions
+            .insert("renewed".to_string(), 5, Duration::from_secs(3600));
+    }
+    {
+        let read = db.r();
+        assert_eq!(None, read.sessions.get("expired"));
+        assert_eq!(Some(&2), read.sessions.get("live"));
+        assert_eq!(Some(&5), read.sessions.get("renewed"));
+        assert_eq!(2, read.sessions.iter().count());
+    }
+    assert_eq!(1, db.w().sessions.remove_expired());
+    assert_eq!(0, db.w().sessions.remove_expired());
+    assert!(db.r().sessions.expires_at("expired").is_none());
+    assert!(db.verify().is_ok());
+    Ok(())
+}
+
+#[test]
+fn background_sweep() -> Result<()> {
+    let dir = TestDir::new();
+    let db: Arc<Db<Data>> = Arc::new(dir.open());
+    db.w()
+        .sessions
+        .insert("short".to_string(), 1, Duration::from_millis(10));
+    db.sweep_every(Duration::from_millis(10), |root| {
+        root.sessions.remove_expired();
+    });
+    let start = Instant::now();
+    while db.r().sessions.expires_at("short").is_some() {
+        assert!(start.elapsed() < Duration::from_secs(10));
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use anyhow::Result;
+use bigobject::{BigMap, BigObject, Db};
+use common::TestDir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct Before {
+    dropped: BigMap<String, i32>,
+    kept: BigMap<String, i32>,
+    retyped: BigMap<String, i32>,
+}
+
+#[derive(Default, BigObject, Serialize, Deserialize)]
+struct After {
+    dropped: (),
+    kept: BigMap<String, i32>,
+    retyped: BigMap<String, String>,
+}
+
+#[test]
+fn verify_and_repair() -> Result<()> {
+    let dir = TestDir::new();
+    {
+        let db: Db<Before> = dir.open();
+        let mut write = db.w();
+        write.dropped.insert("a".to_string(), 1);
+        write.dropped.insert("b".to_string(), 2);
+        write.kept.insert("c".to_string(), 3);
+        write.retyped.insert("d".to_string(), 4);
+    }
+    {
+        let db: Db<Before> = dir.open();
+        let report = db.verify();
+        assert!(report.is_ok());
+        assert_eq!(4, report.checked_keys);
+    }
+    let db: Db<After> = dir.open();
+    let report = db.verify();
+    assert_eq!(2, report.orphaned_keys.len());
+    assert_eq!(1, report.undecodable_keys.len());
+    assert!(report.mismatched_keys.is_empty());
+    let repaired = db.repair();
+    assert_eq!(repaired.undecodable_keys, repaired.unrepairable_keys);
+    let report = db.verify();
+    assert!(report.orphaned_keys.is_empty());
+    assert_eq!(1, report.undecodable_keys.len());
+    assert_eq!(2, report.checked_keys);
+    assert_eq!(Some(&3), db.r().kept.get("c"));
+    Ok(())
+}